@@ -1,5 +1,6 @@
 use clap::{Parser, Subcommand};
 use clap_complete::Shell;
+use claptrap::output::{OutputFormat, VarNaming};
 use std::ffi::OsString;
 use std::path::PathBuf;
 
@@ -10,6 +11,34 @@ pub struct Cli {
     #[arg(short, long, value_name = "FILE", env = "CLAPTRAP_SPEC")]
     pub spec: PathBuf,
 
+    /// The format of the output
+    #[arg(long, value_enum, default_value_t)]
+    pub format: OutputFormat,
+
+    /// Also output all variables as a single associative array
+    #[arg(long)]
+    pub assoc_array: bool,
+
+    /// The prefix of all variable names [default: claptrap]
+    #[arg(long, value_name = "PREFIX")]
+    pub var_prefix: Option<String>,
+
+    /// The convention used to build variable names from ids [default: verbatim]
+    #[arg(long, value_enum, value_name = "NAMING")]
+    pub var_naming: Option<VarNaming>,
+
+    /// Also output the source of the value of each arg (cli, env or default)
+    #[arg(long)]
+    pub value_source: bool,
+
+    /// Also output empty variables for absent args, so that all variables are declared
+    #[arg(long)]
+    pub declare_all: bool,
+
+    /// Also output the indices of each arg and the order in which args occurred
+    #[arg(long)]
+    pub indices: bool,
+
     /// Do not suppress panic messages
     #[arg(long)]
     pub show_panic: bool,
@@ -34,6 +63,8 @@ pub enum SubCommand {
         #[arg(short, long, value_name = "FILE")]
         output: Option<PathBuf>,
     },
+    /// Check the spec for problems, exiting with a nonzero status if any are found
+    Check,
     /// Generate ROFF man page
     Man {
         /// The output file for the ROFF man page
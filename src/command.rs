@@ -1,13 +1,37 @@
+use crate::Settings;
+use crate::output::{DEFAULT_PREFIX, OutputFormat, VarNaming};
+use clap::builder::{ArgPredicate, OsStr, Resettable};
 use indexmap::IndexMap;
+use itertools::Itertools;
 use serde::Deserialize;
+use std::fmt::Display;
+use std::ops::{Bound, Range};
 use std::str::FromStr;
+use strum::VariantNames;
+use toml::Spanned;
+use value_parser::{
+    CompositeValueParser, GlobValueParser, HostnameValueParser, PathValueParser,
+    PatternValueParser, ValueCheck,
+};
+
+pub(crate) use value_parser::CompositeValue;
+
+mod check;
+mod validate;
+mod value_parser;
+
+pub use check::render_check;
+pub use validate::{Diagnostic, SpecSource, render_diagnostics};
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "kebab-case")]
 pub struct Command {
-    name: String,
+    name: Option<String>,
+    #[serde(default)]
     args: IndexMap<String, Arg>,
+    groups: Option<IndexMap<String, ArgGroup>>,
+    subcommands: Option<IndexMap<String, Command>>,
     ignore_errors: Option<bool>,
     args_override_self: Option<bool>,
     dont_delimit_trailing_values: Option<bool>,
@@ -62,210 +86,456 @@ pub struct Command {
     hide: Option<bool>,
     subcommand_required: Option<bool>,
     allow_external_subcommands: Option<bool>,
-    // TODO external_subcommand_value_parser: Option<String>,
+    external_subcommand_value_parser: Option<String>,
     args_conflicts_with_subcommands: Option<bool>,
     subcommand_precedence_over_arg: Option<bool>,
     subcommand_negates_reqs: Option<bool>,
     multicall: Option<bool>,
     subcommand_value_name: Option<String>,
     subcommand_help_heading: Option<String>,
+    /// Non-standard option
+    assoc_array: Option<bool>,
+    /// Non-standard option
+    var_prefix: Option<String>,
+    /// Non-standard option
+    var_naming: Option<VarNaming>,
+    /// Non-standard option
+    value_source: Option<bool>,
+    /// Non-standard option
+    declare_all: Option<bool>,
+    /// Non-standard option
+    indices: Option<bool>,
+    /// The format in which the output is rendered, which is not part of the spec.
+    #[serde(skip)]
+    format: OutputFormat,
+    /// Whether the spec has already been validated by `from_toml`.
+    #[serde(skip)]
+    validated: bool,
+}
+
+impl Command {
+    /// Deserialize a spec from TOML and validate it, returning all problems found.
+    ///
+    /// The spec is then not validated again when parsing arguments.
+    pub fn from_toml(text: &str) -> Result<Self, Vec<Diagnostic>> {
+        let mut cmd = toml::from_str::<Command>(text).map_err(|err| vec![Diagnostic::from(err)])?;
+        let diagnostics = cmd.validate();
+        if diagnostics.is_empty() {
+            cmd.validated = true;
+            Ok(cmd)
+        } else {
+            Err(diagnostics)
+        }
+    }
+
+    /// Whether the spec has already been validated, such that it need not be again.
+    pub(crate) fn is_validated(&self) -> bool {
+        self.validated
+    }
+
+    /// Also output all variables as a single associative array, along with the ids of the
+    /// args which were present on the command line.
+    pub fn assoc_array(mut self, yes: bool) -> Self {
+        self.assoc_array = Some(yes);
+        self
+    }
+
+    /// The prefix of all variable names, `claptrap` by default.
+    pub fn var_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.var_prefix = Some(prefix.into());
+        self
+    }
+
+    /// The convention used to build variable names from ids.
+    pub fn var_naming(mut self, naming: VarNaming) -> Self {
+        self.var_naming = Some(naming);
+        self
+    }
+
+    /// Also output the source of the value of each arg, one of `cli`, `env` or `default`, as
+    /// `<id>_source`.
+    pub fn value_source(mut self, yes: bool) -> Self {
+        self.value_source = Some(yes);
+        self
+    }
+
+    /// Also output every arg, group and subcommand variable which is absent, such that all
+    /// variables are declared.
+    ///
+    /// Absent options are empty, or an empty array if many-valued.
+    pub fn declare_all(mut self, yes: bool) -> Self {
+        self.declare_all = Some(yes);
+        self
+    }
+
+    /// Also output the indices of the values of each arg on the command line as
+    /// `<id>_indices`, and the ids of the args in the order in which they occurred as `order`.
+    ///
+    /// As reported by clap, indices start at 1 and restart at 1 after the name of each
+    /// subcommand, such that they are relative to the args of the command which declares them.
+    pub fn indices(mut self, yes: bool) -> Self {
+        self.indices = Some(yes);
+        self
+    }
+
+    /// The errors in the `index` of each positional, which clap would panic on, along with the
+    /// span of the offending `index`.
+    ///
+    /// Clap numbers positionals without an `index` from 1 in the order in which they are
+    /// declared, and every positional must have a different index which is no greater than
+    /// the number of positionals.
+    pub(crate) fn index_errors(&self) -> Vec<(SpecError, Range<usize>)> {
+        let is_positional = |arg: &Arg| arg.short.is_none() && arg.long.is_none();
+        let count = self.args.values().filter(|arg| is_positional(arg)).count();
+        let mut indices = self
+            .args
+            .iter()
+            .filter(|(_, arg)| is_positional(arg) && arg.index.is_none())
+            .zip(1..)
+            .map(|((name, _), index)| (index, name.as_str()))
+            .collect::<IndexMap<_, _>>();
+        let mut errors = vec![];
+        for (name, arg) in &self.args {
+            let Some(index) = &arg.index else {
+                continue;
+            };
+            let kind = match *index.get_ref() {
+                _ if !is_positional(arg) => SpecErrorKind::MutuallyExclusive(vec!["short", "long"]),
+                0 => SpecErrorKind::Invalid(String::from("must be at least 1")),
+                i if i > count => SpecErrorKind::Invalid(match count {
+                    1 => format!("is {i} but there is only 1 positional"),
+                    _ => format!("is {i} but there are only {count} positionals"),
+                }),
+                i => match indices.get(&i) {
+                    Some(other) => {
+                        SpecErrorKind::Invalid(format!("is {i}, the same as positional `{other}`"))
+                    }
+                    None => {
+                        indices.insert(i, name);
+                        continue;
+                    }
+                },
+            };
+            errors.push((SpecError::arg(name, "index", kind), index.span()));
+        }
+        errors
+    }
+
+    /// The format in which the output will be rendered, `bash` by default.
+    ///
+    /// Only POSIX output derives the `<id>_count` and `<id>_<i>` variables of many-valued
+    /// args, so the format determines which variable names collide.
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// The non-standard settings which control the output.
+    pub(crate) fn settings(&self) -> Settings {
+        Settings {
+            assoc_array: self.assoc_array.unwrap_or_default(),
+            var_prefix: self
+                .var_prefix
+                .clone()
+                .unwrap_or_else(|| DEFAULT_PREFIX.to_string()),
+            var_naming: self.var_naming.unwrap_or_default(),
+            value_source: self.value_source.unwrap_or_default(),
+            declare_all: self.declare_all.unwrap_or_default(),
+            indices: self.indices.unwrap_or_default(),
+            format: self.format,
+        }
+    }
 }
 
-impl From<Command> for clap::Command {
-    fn from(cmd: Command) -> Self {
-        let mut command = clap::Command::new(cmd.name);
+impl TryFrom<Command> for clap::Command {
+    type Error = SpecError;
+
+    fn try_from(cmd: Command) -> Result<Self, Self::Error> {
+        cmd.try_into_clap().map(|(command, _)| command)
+    }
+}
+
+/// The names of the parts of the values of each arg which uses a composite value parser, by
+/// the id of the arg, along with those of each subcommand by its name.
+///
+/// Clap erases the type of the value parser of an arg, so these are kept alongside the
+/// `clap::Command` such that the variables for the parts can be named even when the arg has
+/// no values.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct CompositeParts {
+    args: IndexMap<clap::Id, &'static [&'static str]>,
+    subcommands: IndexMap<String, CompositeParts>,
+}
+
+impl CompositeParts {
+    /// The names of the parts of an arg of the command at `path`, if it uses a composite value
+    /// parser.
+    ///
+    /// A global arg may have been propagated from any of the parents of the command.
+    pub(crate) fn get(&self, path: &[&str], arg: &clap::Arg) -> Option<&'static [&'static str]> {
+        let mut commands = vec![self];
+        for name in path {
+            commands.push(commands.last()?.subcommands.get(*name)?);
+        }
+        let declared_by = if arg.is_global_set() {
+            commands.len()
+        } else {
+            1
+        };
+        commands
+            .iter()
+            .rev()
+            .take(declared_by)
+            .find_map(|parts| parts.args.get(arg.get_id()).copied())
+    }
+}
+
+impl Command {
+    /// Convert the spec into a `clap::Command`, along with the parts of its composite args.
+    pub(crate) fn try_into_clap(self) -> Result<(clap::Command, CompositeParts), SpecError> {
+        let index_error = self.index_errors().into_iter().next();
+        let mut parts = CompositeParts {
+            args: self
+                .args
+                .iter()
+                .filter_map(|(name, arg)| {
+                    let id = arg.id.as_deref().unwrap_or(name);
+                    let parser = TypedValueParser::from_str(arg.typed_value_parser.as_deref()?);
+                    Some((
+                        clap::Id::from(id.to_string()),
+                        parser.ok()?.composite_parts()?,
+                    ))
+                })
+                .collect(),
+            subcommands: IndexMap::new(),
+        };
+        let name = self.name.unwrap_or_default();
+        if let Some((err, _)) = index_error {
+            return Err(err.in_command(&name));
+        }
+        let mut command = clap::Command::new(name.clone());
         command = command.args(
-            cmd.args
+            self.args
                 .into_iter()
-                .map(|(name, arg)| clap::Arg::from(NamedArg::new(name, arg)))
-                .collect::<Vec<_>>(),
+                .map(|(name, arg)| clap::Arg::try_from(NamedArg::new(name, arg)))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| err.in_command(&name))?,
         );
-        // TODO: group
-        // TODO: groups
-        // TODO: subcommand
-        // TODO: subcommands
+        if let Some(groups) = self.groups {
+            command = command.groups(
+                groups
+                    .into_iter()
+                    .map(|(name, group)| clap::ArgGroup::from(NamedArgGroup::new(name, group))),
+            );
+        }
+        if let Some(subcommands) = self.subcommands {
+            command = command.subcommands(
+                subcommands
+                    .into_iter()
+                    .map(|(name, mut subcommand)| {
+                        subcommand.name.get_or_insert(name);
+                        let (subcommand, sub_parts) = subcommand.try_into_clap()?;
+                        parts
+                            .subcommands
+                            .insert(subcommand.get_name().to_string(), sub_parts);
+                        Ok(subcommand)
+                    })
+                    .collect::<Result<Vec<_>, SpecError>>()
+                    .map_err(|err| err.in_command(&name))?,
+            );
+        }
         // TODO: error
-        if let Some(ignore_errors) = cmd.ignore_errors {
+        if let Some(ignore_errors) = self.ignore_errors {
             command = command.ignore_errors(ignore_errors);
         }
-        if let Some(args_override_self) = cmd.args_override_self {
+        if let Some(args_override_self) = self.args_override_self {
             command = command.args_override_self(args_override_self);
         }
-        if let Some(dont_delimit_trailing_values) = cmd.dont_delimit_trailing_values {
+        if let Some(dont_delimit_trailing_values) = self.dont_delimit_trailing_values {
             command = command.dont_delimit_trailing_values(dont_delimit_trailing_values);
         }
-        if let Some(color) = cmd.color {
+        if let Some(color) = self.color {
             command = command.color(clap::ColorChoice::from(color));
         }
         // TODO: styles
-        if let Some(term_width) = cmd.term_width {
+        if let Some(term_width) = self.term_width {
             command = command.term_width(term_width);
         }
-        if let Some(max_term_width) = cmd.max_term_width {
+        if let Some(max_term_width) = self.max_term_width {
             command = command.max_term_width(max_term_width);
         }
-        if let Some(disable_version_flag) = cmd.disable_version_flag {
+        if let Some(disable_version_flag) = self.disable_version_flag {
             command = command.disable_version_flag(disable_version_flag);
         }
-        if let Some(propagate_version) = cmd.propagate_version {
+        if let Some(propagate_version) = self.propagate_version {
             command = command.propagate_version(propagate_version);
         }
-        if let Some(next_line_help) = cmd.next_line_help {
+        if let Some(next_line_help) = self.next_line_help {
             command = command.next_line_help(next_line_help);
         }
-        if let Some(disable_help_flag) = cmd.disable_help_flag {
+        if let Some(disable_help_flag) = self.disable_help_flag {
             command = command.disable_help_flag(disable_help_flag);
         }
-        if let Some(disable_help_subcommand) = cmd.disable_help_subcommand {
+        if let Some(disable_help_subcommand) = self.disable_help_subcommand {
             command = command.disable_help_subcommand(disable_help_subcommand);
         }
-        if let Some(disable_colored_help) = cmd.disable_colored_help {
+        if let Some(disable_colored_help) = self.disable_colored_help {
             command = command.disable_colored_help(disable_colored_help);
         }
-        if let Some(help_expected) = cmd.help_expected {
+        if let Some(help_expected) = self.help_expected {
             command = command.help_expected(help_expected);
         }
-        if let Some(hide_possible_values) = cmd.hide_possible_values {
+        if let Some(hide_possible_values) = self.hide_possible_values {
             command = command.hide_possible_values(hide_possible_values);
         }
-        if let Some(infer_long_args) = cmd.infer_long_args {
+        if let Some(infer_long_args) = self.infer_long_args {
             command = command.infer_long_args(infer_long_args);
         }
-        if let Some(infer_subcommands) = cmd.infer_subcommands {
+        if let Some(infer_subcommands) = self.infer_subcommands {
             command = command.infer_subcommands(infer_subcommands);
         }
-        if let Some(bin_name) = cmd.bin_name {
+        if let Some(bin_name) = self.bin_name {
             command = command.bin_name(bin_name);
         }
-        if let Some(display_name) = cmd.display_name {
+        if let Some(display_name) = self.display_name {
             command = command.display_name(display_name);
         }
-        if let Some(author) = cmd.author {
+        if let Some(author) = self.author {
             command = command.author(author);
         }
-        if let Some(about) = cmd.about {
+        if let Some(about) = self.about {
             command = command.about(about);
         }
-        if let Some(long_about) = cmd.long_about {
+        if let Some(long_about) = self.long_about {
             command = command.long_about(long_about);
         }
-        if let Some(after_help) = cmd.after_help {
+        if let Some(after_help) = self.after_help {
             command = command.after_help(after_help);
         }
-        if let Some(after_long_help) = cmd.after_long_help {
+        if let Some(after_long_help) = self.after_long_help {
             command = command.after_long_help(after_long_help);
         }
-        if let Some(before_help) = cmd.before_help {
+        if let Some(before_help) = self.before_help {
             command = command.before_help(before_help);
         }
-        if let Some(before_long_help) = cmd.before_long_help {
+        if let Some(before_long_help) = self.before_long_help {
             command = command.before_long_help(before_long_help);
         }
-        if let Some(version) = cmd.version {
+        if let Some(version) = self.version {
             command = command.version(version);
         }
-        if let Some(long_version) = cmd.long_version {
+        if let Some(long_version) = self.long_version {
             command = command.long_version(long_version);
         }
-        if let Some(override_usage) = cmd.override_usage {
+        if let Some(override_usage) = self.override_usage {
             command = command.override_usage(override_usage);
         }
-        if let Some(override_help) = cmd.override_help {
+        if let Some(override_help) = self.override_help {
             command = command.override_help(override_help);
         }
-        if let Some(help_template) = cmd.help_template {
+        if let Some(help_template) = self.help_template {
             command = command.help_template(help_template);
         }
-        if let Some(flatten_help) = cmd.flatten_help {
+        if let Some(flatten_help) = self.flatten_help {
             command = command.flatten_help(flatten_help);
         }
-        if let Some(next_help_heading) = cmd.next_help_heading {
+        if let Some(next_help_heading) = self.next_help_heading {
             command = command.next_help_heading(next_help_heading);
         }
-        if let Some(next_display_order) = cmd.next_display_order {
+        if let Some(next_display_order) = self.next_display_order {
             command = command.next_display_order(next_display_order);
         }
-        if let Some(arg_required_else_help) = cmd.arg_required_else_help {
+        if let Some(arg_required_else_help) = self.arg_required_else_help {
             command = command.arg_required_else_help(arg_required_else_help);
         }
-        if let Some(allow_missing_positional) = cmd.allow_missing_positional {
+        if let Some(allow_missing_positional) = self.allow_missing_positional {
             command = command.allow_missing_positional(allow_missing_positional);
         }
-        if let Some(short_flag) = cmd.short_flag {
+        if let Some(short_flag) = self.short_flag {
             command = command.short_flag(short_flag);
         }
-        if let Some(long_flag) = cmd.long_flag {
+        if let Some(long_flag) = self.long_flag {
             command = command.long_flag(long_flag);
         }
-        if let Some(alias) = cmd.alias {
+        if let Some(alias) = self.alias {
             command = command.alias(alias);
         }
-        if let Some(short_flag_alias) = cmd.short_flag_alias {
+        if let Some(short_flag_alias) = self.short_flag_alias {
             command = command.short_flag_alias(short_flag_alias);
         }
-        if let Some(long_flag_alias) = cmd.long_flag_alias {
+        if let Some(long_flag_alias) = self.long_flag_alias {
             command = command.long_flag_alias(long_flag_alias);
         }
-        if let Some(aliases) = cmd.aliases {
+        if let Some(aliases) = self.aliases {
             command = command.aliases(aliases);
         }
-        if let Some(short_flag_aliases) = cmd.short_flag_aliases {
+        if let Some(short_flag_aliases) = self.short_flag_aliases {
             command = command.short_flag_aliases(short_flag_aliases);
         }
-        if let Some(long_flag_aliases) = cmd.long_flag_aliases {
+        if let Some(long_flag_aliases) = self.long_flag_aliases {
             command = command.long_flag_aliases(long_flag_aliases);
         }
-        if let Some(visible_alias) = cmd.visible_alias {
+        if let Some(visible_alias) = self.visible_alias {
             command = command.visible_alias(visible_alias);
         }
-        if let Some(visible_short_flag_alias) = cmd.visible_short_flag_alias {
+        if let Some(visible_short_flag_alias) = self.visible_short_flag_alias {
             command = command.visible_short_flag_alias(visible_short_flag_alias);
         }
-        if let Some(visible_long_flag_alias) = cmd.visible_long_flag_alias {
+        if let Some(visible_long_flag_alias) = self.visible_long_flag_alias {
             command = command.visible_long_flag_alias(visible_long_flag_alias);
         }
-        if let Some(visible_aliases) = cmd.visible_aliases {
+        if let Some(visible_aliases) = self.visible_aliases {
             command = command.visible_aliases(visible_aliases);
         }
-        if let Some(visible_short_flag_aliases) = cmd.visible_short_flag_aliases {
+        if let Some(visible_short_flag_aliases) = self.visible_short_flag_aliases {
             command = command.visible_short_flag_aliases(visible_short_flag_aliases);
         }
-        if let Some(visible_long_flag_aliases) = cmd.visible_long_flag_aliases {
+        if let Some(visible_long_flag_aliases) = self.visible_long_flag_aliases {
             command = command.visible_long_flag_aliases(visible_long_flag_aliases);
         }
-        if let Some(display_order) = cmd.display_order {
+        if let Some(display_order) = self.display_order {
             command = command.display_order(display_order);
         }
-        if let Some(hide) = cmd.hide {
+        if let Some(hide) = self.hide {
             command = command.hide(hide);
         }
-        if let Some(subcommand_required) = cmd.subcommand_required {
+        if let Some(subcommand_required) = self.subcommand_required {
             command = command.subcommand_required(subcommand_required);
         }
-        if let Some(allow_external_subcommands) = cmd.allow_external_subcommands {
+        if let Some(allow_external_subcommands) = self.allow_external_subcommands {
             command = command.allow_external_subcommands(allow_external_subcommands);
         }
-        // TODO: external_subcommand_value_parser
-        if let Some(args_conflicts_with_subcommands) = cmd.args_conflicts_with_subcommands {
+        if let Some(value_parser) = self.external_subcommand_value_parser {
+            let key = "external-subcommand-value-parser";
+            let value_parser = TypedValueParser::from_str(&value_parser)
+                .map_err(|_| SpecErrorKind::InvalidValue {
+                    value: value_parser.clone(),
+                    expected: TypedValueParser::VARIANTS,
+                })
+                .and_then(|parser| parser.value_parser(None, None).map_err(|(_, kind)| kind))
+                .map_err(|kind| SpecError::command(key, kind).in_command(&name))?;
+            command = command.external_subcommand_value_parser(value_parser);
+        }
+        if let Some(args_conflicts_with_subcommands) = self.args_conflicts_with_subcommands {
             command = command.args_conflicts_with_subcommands(args_conflicts_with_subcommands);
         }
-        if let Some(subcommand_precedence_over_arg) = cmd.subcommand_precedence_over_arg {
+        if let Some(subcommand_precedence_over_arg) = self.subcommand_precedence_over_arg {
             command = command.subcommand_precedence_over_arg(subcommand_precedence_over_arg);
         }
-        if let Some(subcommand_negates_reqs) = cmd.subcommand_negates_reqs {
+        if let Some(subcommand_negates_reqs) = self.subcommand_negates_reqs {
             command = command.subcommand_negates_reqs(subcommand_negates_reqs);
         }
-        if let Some(multicall) = cmd.multicall {
+        if let Some(multicall) = self.multicall {
             command = command.multicall(multicall);
         }
-        if let Some(subcommand_value_name) = cmd.subcommand_value_name {
+        if let Some(subcommand_value_name) = self.subcommand_value_name {
             command = command.subcommand_value_name(subcommand_value_name);
         }
-        if let Some(subcommand_help_heading) = cmd.subcommand_help_heading {
+        if let Some(subcommand_help_heading) = self.subcommand_help_heading {
             command = command.subcommand_help_heading(subcommand_help_heading);
         }
 
-        command
+        Ok((command, parts))
     }
 }
 
@@ -314,15 +584,15 @@ pub struct Arg {
     visible_short_alias: Option<char>,
     visible_aliases: Option<Vec<String>>,
     visible_short_aliases: Option<Vec<char>>,
-    index: Option<usize>,
+    index: Option<Spanned<usize>>,
     trailing_var_arg: Option<bool>,
     last: Option<bool>,
     required: Option<bool>,
-    requires: Option<String>,
+    requires: Option<Spanned<String>>,
     exclusive: Option<bool>,
     global: Option<bool>,
     action: Option<ArgAction>,
-    value_parser: Option<Vec<String>>,
+    value_parser: Option<Vec<PossibleValue>>,
     num_args: Option<usize>,
     value_name: Option<String>,
     value_names: Option<Vec<String>>,
@@ -334,13 +604,16 @@ pub struct Arg {
     value_delimiter: Option<char>,
     value_terminator: Option<String>,
     raw: Option<bool>,
-    default_value: Option<String>,            // TODO is really OsString
-    default_values: Option<Vec<String>>,      // TODO is really Vec<OsString>
-    default_missing_value: Option<String>,    // TODO is really OsString
-    default_missing_value_os: Option<String>, // TODO is really OsString
-    default_missing_values: Option<Vec<String>>, // TODO is really Vec<OsString>
-    default_missing_values_os: Option<Vec<String>>, // TODO is really Vec<OsString>
-    env: Option<String>,                      // TODO is really OsString
+    // Clap accepts `OsStr` defaults and env var names, but TOML strings are always valid UTF-8
+    // and so these are kept as `String`s, even for args which accept values that are not, such
+    // as those using the `os-string` or `path` value parsers.
+    default_value: Option<String>,
+    default_values: Option<Vec<String>>,
+    default_missing_value: Option<String>,
+    default_missing_value_os: Option<String>,
+    default_missing_values: Option<Vec<String>>,
+    default_missing_values_os: Option<Vec<String>>,
+    env: Option<String>,
     help: Option<String>,
     long_help: Option<String>,
     display_order: Option<usize>,
@@ -355,32 +628,44 @@ pub struct Arg {
     hide_long_help: Option<bool>,
     group: Option<String>,
     groups: Option<Vec<String>>,
-    // default_value_if // TODO
-    // default_value_ifs // TODO
-    required_unless_present: Option<String>,
-    required_unless_present_all: Option<Vec<String>>,
-    required_unless_present_any: Option<Vec<String>>,
-    // required_if_eq // TODO
-    // required_if_eq_any // TODO
-    // required_if_eq_all // TODO
-    // requires_if // TODO
-    // requires_ifs // TODO
-    conflicts_with: Option<String>,
-    conflicts_with_all: Option<Vec<String>>,
-    overrides_with: Option<String>,
-    overrides_with_all: Option<Vec<String>>,
+    default_value_if: Option<DefaultValueIf>,
+    default_value_ifs: Option<Vec<DefaultValueIf>>,
+    required_unless_present: Option<Spanned<String>>,
+    required_unless_present_all: Option<Vec<Spanned<String>>>,
+    required_unless_present_any: Option<Vec<Spanned<String>>>,
+    required_if_eq: Option<ArgValue>,
+    required_if_eq_any: Option<Vec<ArgValue>>,
+    required_if_eq_all: Option<Vec<ArgValue>>,
+    requires_if: Option<RequiresIf>,
+    requires_ifs: Option<Vec<RequiresIf>>,
+    conflicts_with: Option<Spanned<String>>,
+    conflicts_with_all: Option<Vec<Spanned<String>>>,
+    overrides_with: Option<Spanned<String>>,
+    overrides_with_all: Option<Vec<Spanned<String>>>,
     /// Non-standard option
     typed_value_parser: Option<String>,
     /// Non-standard option
     min_args: Option<usize>,
     /// Non-standard option
     max_args: Option<usize>,
+    /// Non-standard option
+    min_value: Option<i64>,
+    /// Non-standard option
+    max_value: Option<i64>,
+    /// Non-standard option
+    value_pattern: Option<String>,
+    /// Non-standard option
+    value_glob: Option<String>,
+    /// Non-standard option
+    path: Option<PathSpec>,
 }
 
-impl From<NamedArg> for clap::Arg {
-    fn from(named_arg: NamedArg) -> Self {
+impl TryFrom<NamedArg> for clap::Arg {
+    type Error = SpecError;
+
+    fn try_from(named_arg: NamedArg) -> Result<Self, Self::Error> {
         let value = named_arg.arg;
-        let mut arg = clap::Arg::new(named_arg.name);
+        let mut arg = clap::Arg::new(named_arg.name.clone());
         if let Some(id) = value.id {
             arg = arg.id(id);
         }
@@ -415,9 +700,8 @@ impl From<NamedArg> for clap::Arg {
         if let Some(visible_short_aliases) = value.visible_short_aliases {
             arg = arg.visible_short_aliases(visible_short_aliases);
         }
-        // TODO can panic, check clap docs and add tests
         if let Some(index) = value.index {
-            arg = arg.index(index);
+            arg = arg.index(index.into_inner());
         }
         if let Some(trailing_var_arg) = value.trailing_var_arg {
             arg = arg.trailing_var_arg(trailing_var_arg);
@@ -429,7 +713,7 @@ impl From<NamedArg> for clap::Arg {
             arg = arg.required(required);
         }
         if let Some(requires) = value.requires {
-            arg = arg.requires(requires);
+            arg = arg.requires(requires.into_inner());
         }
         if let Some(exclusive) = value.exclusive {
             arg = arg.exclusive(exclusive);
@@ -441,38 +725,108 @@ impl From<NamedArg> for clap::Arg {
             arg = arg.action(clap::ArgAction::from(action));
         }
 
+        let has_value_parser = value.value_parser.is_some() || value.typed_value_parser.is_some();
+        let check = match (value.value_pattern, value.value_glob) {
+            (Some(_), Some(_)) => {
+                return Err(SpecError::arg(
+                    &named_arg.name,
+                    "value-pattern",
+                    SpecErrorKind::MutuallyExclusive(vec!["value-glob"]),
+                ));
+            }
+            (Some(pattern), None) => {
+                let parser = PatternValueParser::new(&pattern).map_err(|err| {
+                    SpecError::arg(
+                        &named_arg.name,
+                        "value-pattern",
+                        SpecErrorKind::Invalid(format!("is not a valid regex: {err}")),
+                    )
+                })?;
+                Some(("value-pattern", ValueCheck::Pattern(parser)))
+            }
+            (None, Some(glob)) => {
+                let parser = GlobValueParser::new(&glob).map_err(|err| {
+                    SpecError::arg(
+                        &named_arg.name,
+                        "value-glob",
+                        SpecErrorKind::Invalid(format!("is not a valid glob: {err}")),
+                    )
+                })?;
+                Some(("value-glob", ValueCheck::Glob(parser)))
+            }
+            (None, None) => None,
+        };
+        let validator = match (value.path, check) {
+            (Some(path), check) => {
+                let parser = PathValueParser::new(path, check.map(|(_, check)| check));
+                Some(("path", clap::builder::ValueParser::new(parser)))
+            }
+            (None, Some((key, check))) => Some((key, clap::builder::ValueParser::new(check))),
+            (None, None) => None,
+        };
+        if let Some((key, validator)) = validator {
+            // The validators replace the value parser and so cannot be combined with one.
+            if has_value_parser {
+                return Err(SpecError::arg(
+                    &named_arg.name,
+                    key,
+                    SpecErrorKind::MutuallyExclusive(vec!["value-parser", "typed-value-parser"]),
+                ));
+            }
+            arg = arg.value_parser(validator);
+        }
+
         match (value.value_parser, value.typed_value_parser) {
             (Some(_), Some(_)) => {
-                panic!(
-                    "value_parser and typed_value_parser are mutually exclusive. Use one or the other."
-                );
+                return Err(SpecError::arg(
+                    &named_arg.name,
+                    "value-parser",
+                    SpecErrorKind::MutuallyExclusive(vec!["typed-value-parser"]),
+                ));
             }
             (Some(value_parser), None) => {
-                arg = arg.value_parser(value_parser);
+                no_bounds(value.min_value, value.max_value)
+                    .map_err(|(key, kind)| SpecError::arg(&named_arg.name, key, kind))?;
+                arg = arg.value_parser(possible_values_parser(
+                    value_parser
+                        .into_iter()
+                        .map(clap::builder::PossibleValue::from),
+                ));
             }
             (None, Some(typed_value_parser)) => {
-                let value_parser = match TypedValueParser::from_str(&typed_value_parser).unwrap() {
-                    TypedValueParser::Bool => {
-                        clap::builder::ValueParser::new(clap::builder::BoolValueParser::new())
-                    }
-                    TypedValueParser::Boolish => {
-                        clap::builder::ValueParser::new(clap::builder::BoolishValueParser::new())
-                    }
-                    TypedValueParser::Falsey => {
-                        clap::builder::ValueParser::new(clap::builder::FalseyValueParser::new())
-                    }
-                };
+                let typed_value_parser =
+                    TypedValueParser::from_str(&typed_value_parser).map_err(|_| {
+                        SpecError::arg(
+                            &named_arg.name,
+                            "typed-value-parser",
+                            SpecErrorKind::InvalidValue {
+                                value: typed_value_parser.clone(),
+                                expected: TypedValueParser::VARIANTS,
+                            },
+                        )
+                    })?;
+                let value_parser = typed_value_parser
+                    .value_parser(value.min_value, value.max_value)
+                    .map_err(|(key, kind)| SpecError::arg(&named_arg.name, key, kind))?;
                 arg = arg.value_parser(value_parser);
             }
-            _ => {}
+            (None, None) => {
+                no_bounds(value.min_value, value.max_value)
+                    .map_err(|(key, kind)| SpecError::arg(&named_arg.name, key, kind))?;
+            }
         }
 
         match (value.num_args, value.min_args, value.max_args) {
-            (Some(_), Some(_), None) | (Some(_), None, Some(_)) | (Some(_), Some(_), Some(_)) => {
-                // TODO how to handle this custom error
-                panic!(
-                    "num_args, min_args, and max_args are mutually exclusive. Use num_args to set both min and max, or use min_args and max_args to set them separately."
-                );
+            (Some(_), min_args, max_args) if min_args.is_some() || max_args.is_some() => {
+                let others = [("min-args", min_args), ("max-args", max_args)]
+                    .into_iter()
+                    .filter_map(|(key, value)| value.map(|_| key))
+                    .collect();
+                return Err(SpecError::arg(
+                    &named_arg.name,
+                    "num-args",
+                    SpecErrorKind::MutuallyExclusive(others),
+                ));
             }
             (Some(num_args), _, _) => {
                 arg = arg.num_args(clap::builder::ValueRange::new(num_args));
@@ -581,35 +935,388 @@ impl From<NamedArg> for clap::Arg {
         if let Some(groups) = value.groups {
             arg = arg.groups(groups);
         }
-        // TODO: default_value_if
-        // TODO: default_value_ifs
+        if let Some(default_value_if) = value.default_value_if {
+            let (arg_id, predicate, default) = default_value_if.into_parts();
+            arg = arg.default_value_if(arg_id, predicate, default);
+        }
+        if let Some(default_value_ifs) = value.default_value_ifs {
+            arg = arg.default_value_ifs(
+                default_value_ifs
+                    .into_iter()
+                    .map(DefaultValueIf::into_parts),
+            );
+        }
         if let Some(required_unless_present) = value.required_unless_present {
-            arg = arg.required_unless_present(required_unless_present);
+            arg = arg.required_unless_present(required_unless_present.into_inner());
         }
         if let Some(required_unless_present_all) = value.required_unless_present_all {
-            arg = arg.required_unless_present_all(required_unless_present_all);
+            arg = arg.required_unless_present_all(
+                required_unless_present_all
+                    .into_iter()
+                    .map(Spanned::into_inner),
+            );
         }
         if let Some(required_unless_present_any) = value.required_unless_present_any {
-            arg = arg.required_unless_present_any(required_unless_present_any);
+            arg = arg.required_unless_present_any(
+                required_unless_present_any
+                    .into_iter()
+                    .map(Spanned::into_inner),
+            );
+        }
+        if let Some(required_if_eq) = value.required_if_eq {
+            let (arg_id, value) = required_if_eq.into_parts();
+            arg = arg.required_if_eq(arg_id, value);
+        }
+        if let Some(required_if_eq_any) = value.required_if_eq_any {
+            arg = arg.required_if_eq_any(required_if_eq_any.into_iter().map(ArgValue::into_parts));
+        }
+        if let Some(required_if_eq_all) = value.required_if_eq_all {
+            arg = arg.required_if_eq_all(required_if_eq_all.into_iter().map(ArgValue::into_parts));
+        }
+        if let Some(requires_if) = value.requires_if {
+            let (predicate, arg_id) = requires_if.into_parts();
+            arg = arg.requires_if(predicate, arg_id);
+        }
+        if let Some(requires_ifs) = value.requires_ifs {
+            arg = arg.requires_ifs(requires_ifs.into_iter().map(RequiresIf::into_parts));
         }
-        // TODO: required_if_eq
-        // TODO: required_if_eq_any
-        // TODO: required_if_eq_all
-        // TODO: requires_if
-        // TODO: requires_ifs
         if let Some(conflicts_with) = value.conflicts_with {
-            arg = arg.conflicts_with(conflicts_with);
+            arg = arg.conflicts_with(conflicts_with.into_inner());
         }
         if let Some(conflicts_with_all) = value.conflicts_with_all {
-            arg = arg.conflicts_with_all(conflicts_with_all);
+            arg = arg.conflicts_with_all(conflicts_with_all.into_iter().map(Spanned::into_inner));
         }
         if let Some(overrides_with) = value.overrides_with {
-            arg = arg.overrides_with(overrides_with);
+            arg = arg.overrides_with(overrides_with.into_inner());
         }
         if let Some(overrides_with_all) = value.overrides_with_all {
-            arg = arg.overrides_with_all(overrides_with_all);
+            arg = arg.overrides_with_all(overrides_with_all.into_iter().map(Spanned::into_inner));
         }
-        arg
+        Ok(arg)
+    }
+}
+
+/// A default value for an arg which applies when another arg is present or, if `value` is
+/// set, when the other arg has that value.
+///
+/// For example `{ arg = "tls", default = "443" }`.  If `default` is not set then the arg
+/// has no default value when the condition holds.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+pub struct DefaultValueIf {
+    arg: Spanned<String>,
+    value: Option<String>,
+    default: Option<String>,
+}
+
+impl DefaultValueIf {
+    fn into_parts(self) -> (String, ArgPredicate, Resettable<OsStr>) {
+        let default = match self.default {
+            Some(default) => Resettable::Value(OsStr::from(default)),
+            None => Resettable::Reset,
+        };
+        (self.arg.into_inner(), predicate(self.value), default)
+    }
+}
+
+/// Another arg and the value it must have, for example `{ arg = "mode", value = "tls" }`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+pub struct ArgValue {
+    arg: Spanned<String>,
+    value: String,
+}
+
+impl ArgValue {
+    fn into_parts(self) -> (String, String) {
+        (self.arg.into_inner(), self.value)
+    }
+}
+
+/// Another arg which is required when this arg is present or, if `value` is set, when this
+/// arg has that value.
+///
+/// For example `{ value = "tls", arg = "cert" }`.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+pub struct RequiresIf {
+    arg: Spanned<String>,
+    value: Option<String>,
+}
+
+impl RequiresIf {
+    fn into_parts(self) -> (ArgPredicate, String) {
+        (predicate(self.value), self.arg.into_inner())
+    }
+}
+
+/// The predicate for an optional value, which holds if the arg is present when no value is
+/// given.
+fn predicate(value: Option<String>) -> ArgPredicate {
+    value.map_or(ArgPredicate::IsPresent, ArgPredicate::from)
+}
+
+/// Checks of a path value against the filesystem, for example
+/// `{ must-exist = true, kind = "dir", writable = true }`.
+///
+/// The `kind` is only checked if the path exists.  A path which does not exist is
+/// `writable` if its parent directory is writable.  When `canonicalize` is set the value is
+/// output as an absolute path, with any symlinks resolved if the path exists.
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+pub struct PathSpec {
+    must_exist: Option<bool>,
+    kind: Option<PathKind>,
+    readable: Option<bool>,
+    writable: Option<bool>,
+    executable: Option<bool>,
+    canonicalize: Option<bool>,
+}
+
+/// The kind of filesystem entry a path must refer to.
+#[derive(Debug, Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum PathKind {
+    File,
+    Dir,
+}
+
+/// A possible value of an arg, either a name or a table such as
+/// `{ name = "icmp", help = "...", aliases = ["i"], hide = true }`.
+#[derive(Debug, Clone)]
+pub struct PossibleValue {
+    name: String,
+    help: Option<String>,
+    aliases: Option<Vec<String>>,
+    hide: Option<bool>,
+}
+
+impl<'de> Deserialize<'de> for PossibleValue {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        #[serde(rename_all = "kebab-case")]
+        struct Table {
+            name: String,
+            help: Option<String>,
+            aliases: Option<Vec<String>>,
+            hide: Option<bool>,
+        }
+
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = PossibleValue;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a string or a table with a `name`")
+            }
+
+            fn visit_str<E: serde::de::Error>(self, name: &str) -> Result<Self::Value, E> {
+                Ok(PossibleValue {
+                    name: name.to_string(),
+                    help: None,
+                    aliases: None,
+                    hide: None,
+                })
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                map: A,
+            ) -> Result<Self::Value, A::Error> {
+                let table = Table::deserialize(serde::de::value::MapAccessDeserializer::new(map))?;
+                Ok(PossibleValue {
+                    name: table.name,
+                    help: table.help,
+                    aliases: table.aliases,
+                    hide: table.hide,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
+impl From<PossibleValue> for clap::builder::PossibleValue {
+    fn from(value: PossibleValue) -> Self {
+        let mut possible_value = clap::builder::PossibleValue::new(value.name);
+        if let Some(help) = value.help {
+            possible_value = possible_value.help(help);
+        }
+        if let Some(aliases) = value.aliases {
+            possible_value = possible_value.aliases(aliases);
+        }
+        if let Some(hide) = value.hide {
+            possible_value = possible_value.hide(hide);
+        }
+        possible_value
+    }
+}
+
+/// A parser which accepts any of the possible values, or their aliases, and always yields
+/// the canonical name of the matched value.
+///
+/// Clap's own `PossibleValuesParser` yields the value as given, which may be an alias or,
+/// when `ignore-case` is set, differ in case from the name.
+fn possible_values_parser(
+    possible_values: impl IntoIterator<Item = clap::builder::PossibleValue>,
+) -> impl clap::builder::TypedValueParser<Value = String> {
+    use clap::builder::TypedValueParser as _;
+    let possible_values = possible_values.into_iter().collect::<Vec<_>>();
+    let canonical = possible_values.clone();
+    clap::builder::PossibleValuesParser::new(possible_values).map(move |value| {
+        // An exact match takes precedence over one which only differs in case.
+        canonical
+            .iter()
+            .find(|possible_value| possible_value.matches(&value, false))
+            .or_else(|| {
+                canonical
+                    .iter()
+                    .find(|possible_value| possible_value.matches(&value, true))
+            })
+            .map_or(value, |possible_value| {
+                possible_value.get_name().to_string()
+            })
+    })
+}
+
+/// An error in a spec which prevents it from being converted into a `clap::Command`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SpecError {
+    /// The names of the command and any subcommands, outermost first.
+    pub command: Vec<String>,
+    /// The name of the arg, if the error relates to an arg.
+    pub arg: Option<String>,
+    /// The spec key which is in error, for example `num-args`.
+    pub key: &'static str,
+    pub kind: SpecErrorKind,
+}
+
+/// The kind of a `SpecError`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SpecErrorKind {
+    /// The key cannot be used together with any of these keys.
+    MutuallyExclusive(Vec<&'static str>),
+    /// The value of the key is not one of the expected values.
+    InvalidValue {
+        value: String,
+        expected: &'static [&'static str],
+    },
+    /// The key is invalid for the given reason.
+    Invalid(String),
+}
+
+impl SpecError {
+    fn arg(name: &str, key: &'static str, kind: SpecErrorKind) -> Self {
+        Self {
+            command: vec![],
+            arg: Some(name.to_string()),
+            key,
+            kind,
+        }
+    }
+
+    fn command(key: &'static str, kind: SpecErrorKind) -> Self {
+        Self {
+            command: vec![],
+            arg: None,
+            key,
+            kind,
+        }
+    }
+
+    /// Record that the error occurred within the named (sub)command.
+    fn in_command(mut self, name: &str) -> Self {
+        self.command.insert(0, name.to_string());
+        self
+    }
+}
+
+impl Display for SpecError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let command = self.command.join(" ");
+        match &self.arg {
+            Some(arg) => write!(f, "invalid arg `{arg}` of command `{command}`: ")?,
+            None => write!(f, "invalid command `{command}`: ")?,
+        }
+        match &self.kind {
+            SpecErrorKind::MutuallyExclusive(others) => write!(
+                f,
+                "`{}` cannot be used with {}",
+                self.key,
+                others.iter().map(|other| format!("`{other}`")).join(" or ")
+            ),
+            SpecErrorKind::InvalidValue { value, expected } => write!(
+                f,
+                "invalid value `{value}` for `{}`, expected one of {}",
+                self.key,
+                expected.iter().map(|value| format!("`{value}`")).join(", ")
+            ),
+            SpecErrorKind::Invalid(reason) => write!(f, "`{}` {reason}", self.key),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+#[derive(Debug)]
+pub struct NamedArgGroup {
+    pub name: String,
+    pub group: ArgGroup,
+}
+
+impl NamedArgGroup {
+    pub fn new(name: String, group: ArgGroup) -> Self {
+        Self { name, group }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+pub struct ArgGroup {
+    args: Option<Vec<Spanned<String>>>,
+    multiple: Option<bool>,
+    required: Option<bool>,
+    requires: Option<Spanned<String>>,
+    requires_all: Option<Vec<Spanned<String>>>,
+    conflicts_with: Option<Spanned<String>>,
+    conflicts_with_all: Option<Vec<Spanned<String>>>,
+}
+
+impl From<NamedArgGroup> for clap::ArgGroup {
+    fn from(named_group: NamedArgGroup) -> Self {
+        let value = named_group.group;
+        let mut group = clap::ArgGroup::new(named_group.name);
+        if let Some(args) = value.args {
+            group = group.args(args.into_iter().map(Spanned::into_inner));
+        }
+        if let Some(multiple) = value.multiple {
+            group = group.multiple(multiple);
+        }
+        if let Some(required) = value.required {
+            group = group.required(required);
+        }
+        if let Some(requires) = value.requires {
+            group = group.requires(requires.into_inner());
+        }
+        if let Some(requires_all) = value.requires_all {
+            group = group.requires_all(requires_all.into_iter().map(Spanned::into_inner));
+        }
+        if let Some(conflicts_with) = value.conflicts_with {
+            group = group.conflicts_with(conflicts_with.into_inner());
+        }
+        if let Some(conflicts_with_all) = value.conflicts_with_all {
+            group =
+                group.conflicts_with_all(conflicts_with_all.into_iter().map(Spanned::into_inner));
+        }
+        group
     }
 }
 
@@ -683,10 +1390,133 @@ impl From<ValueHint> for clap::ValueHint {
     }
 }
 
-#[derive(Debug, Clone, strum::EnumString)]
+#[derive(Debug, Clone, strum::EnumString, strum::VariantNames)]
 #[strum(serialize_all = "kebab-case")]
 enum TypedValueParser {
     Bool,
     Boolish,
     Falsey,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+    F32,
+    F64,
+    Path,
+    NonEmptyString,
+    OsString,
+    Duration,
+    Bytes,
+    Ipv4,
+    Ipv6,
+    Ip,
+    Cidr,
+    SocketAddr,
+    Port,
+    Hostname,
+    Url,
+}
+
+impl TypedValueParser {
+    /// Build the value parser, restricted to the `min` and `max` bounds for integer parsers.
+    ///
+    /// On error, returns the spec key which is in error and the kind of error.
+    fn value_parser(
+        self,
+        min: Option<i64>,
+        max: Option<i64>,
+    ) -> Result<clap::builder::ValueParser, (&'static str, SpecErrorKind)> {
+        use clap::builder::{RangedI64ValueParser, RangedU64ValueParser, ValueParser};
+        let signed = (
+            min.map_or(Bound::Unbounded, Bound::Included),
+            max.map_or(Bound::Unbounded, Bound::Included),
+        );
+        let unsigned = || {
+            let bound = |key, value: Option<i64>| match value.map(u64::try_from) {
+                None => Ok(Bound::Unbounded),
+                Some(Ok(value)) => Ok(Bound::Included(value)),
+                Some(Err(_)) => Err((
+                    key,
+                    SpecErrorKind::Invalid(String::from(
+                        "must not be negative for an unsigned `typed-value-parser`",
+                    )),
+                )),
+            };
+            Ok((bound("min-value", min)?, bound("max-value", max)?))
+        };
+        let is_integer = matches!(
+            self,
+            TypedValueParser::I8
+                | TypedValueParser::I16
+                | TypedValueParser::I32
+                | TypedValueParser::I64
+                | TypedValueParser::U8
+                | TypedValueParser::U16
+                | TypedValueParser::U32
+                | TypedValueParser::U64
+        );
+        if !is_integer {
+            no_bounds(min, max)?;
+        }
+        Ok(match self {
+            TypedValueParser::I8 => RangedI64ValueParser::<i8>::new().range(signed).into(),
+            TypedValueParser::I16 => RangedI64ValueParser::<i16>::new().range(signed).into(),
+            TypedValueParser::I32 => RangedI64ValueParser::<i32>::new().range(signed).into(),
+            TypedValueParser::I64 => RangedI64ValueParser::<i64>::new().range(signed).into(),
+            TypedValueParser::U8 => RangedU64ValueParser::<u8>::new().range(unsigned()?).into(),
+            TypedValueParser::U16 => RangedU64ValueParser::<u16>::new().range(unsigned()?).into(),
+            TypedValueParser::U32 => RangedU64ValueParser::<u32>::new().range(unsigned()?).into(),
+            TypedValueParser::U64 => RangedU64ValueParser::<u64>::new().range(unsigned()?).into(),
+            TypedValueParser::Bool => ValueParser::bool(),
+            TypedValueParser::Boolish => ValueParser::new(clap::builder::BoolishValueParser::new()),
+            TypedValueParser::Falsey => ValueParser::new(clap::builder::FalseyValueParser::new()),
+            TypedValueParser::F32 => clap::value_parser!(f32).into(),
+            TypedValueParser::F64 => clap::value_parser!(f64).into(),
+            TypedValueParser::Path => ValueParser::path_buf(),
+            TypedValueParser::NonEmptyString => {
+                ValueParser::new(clap::builder::NonEmptyStringValueParser::new())
+            }
+            TypedValueParser::OsString => ValueParser::os_string(),
+            TypedValueParser::Duration => ValueParser::new(CompositeValueParser::duration()),
+            TypedValueParser::Bytes => ValueParser::new(CompositeValueParser::bytes()),
+            TypedValueParser::Ipv4 => clap::value_parser!(std::net::Ipv4Addr).into(),
+            TypedValueParser::Ipv6 => clap::value_parser!(std::net::Ipv6Addr).into(),
+            TypedValueParser::Ip => clap::value_parser!(std::net::IpAddr).into(),
+            TypedValueParser::Cidr => ValueParser::new(CompositeValueParser::cidr()),
+            TypedValueParser::SocketAddr => ValueParser::new(CompositeValueParser::socket_addr()),
+            TypedValueParser::Port => RangedU64ValueParser::<u16>::new().range(1..=65535).into(),
+            TypedValueParser::Hostname => ValueParser::new(HostnameValueParser),
+            TypedValueParser::Url => ValueParser::new(CompositeValueParser::url()),
+        })
+    }
+
+    /// The names of the parts of the values of a composite value parser, if any.
+    fn composite_parts(&self) -> Option<&'static [&'static str]> {
+        let parser = match self {
+            TypedValueParser::Duration => CompositeValueParser::duration(),
+            TypedValueParser::Bytes => CompositeValueParser::bytes(),
+            TypedValueParser::Cidr => CompositeValueParser::cidr(),
+            TypedValueParser::SocketAddr => CompositeValueParser::socket_addr(),
+            TypedValueParser::Url => CompositeValueParser::url(),
+            _ => return None,
+        };
+        Some(parser.parts())
+    }
+}
+
+/// Check that no bounds are set, as only integer value parsers support them.
+fn no_bounds(min: Option<i64>, max: Option<i64>) -> Result<(), (&'static str, SpecErrorKind)> {
+    let key = match (min, max) {
+        (Some(_), _) => "min-value",
+        (None, Some(_)) => "max-value",
+        (None, None) => return Ok(()),
+    };
+    Err((
+        key,
+        SpecErrorKind::Invalid(String::from("requires an integer `typed-value-parser`")),
+    ))
 }
@@ -0,0 +1,377 @@
+use crate::command::{Command, Diagnostic, SpecSource};
+use crate::output::{OutputFormat, is_ident_char};
+use clap::ArgAction;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use serde::Serialize;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
+impl Command {
+    /// Check the spec for problems without parsing any arguments.
+    ///
+    /// As well as validating id references, this applies lints for problems which clap does
+    /// not consider to be errors, or only reports in its debug asserts.  The debug asserts are
+    /// also run as a best-effort extra when the lints find no such problem, but only have an
+    /// effect when clap is built with debug assertions, and panic and so the caller may wish
+    /// to suppress the panic hook.
+    pub fn check(self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.validate();
+        let is_valid = diagnostics.is_empty();
+        let settings = self.settings();
+        let name = self.name.clone().unwrap_or_default();
+        lint_help(&self, false, &[&name], &mut diagnostics);
+        let linted = diagnostics.len();
+        lint_indices(&self, &[&name], &mut diagnostics);
+        if diagnostics.len() > linted {
+            // The conversion would fail on the first of these.
+            return diagnostics;
+        }
+        let (clap_cmd, composite_parts) = match self.try_into_clap() {
+            Ok(converted) => converted,
+            Err(err) => {
+                diagnostics.push(Diagnostic::new("invalid-spec", err.to_string(), None));
+                return diagnostics;
+            }
+        };
+        lint_command(&clap_cmd, &settings, false, &[], &mut diagnostics);
+        // The debug asserts would also fail for any unknown ids, which are already reported,
+        // and for the problems found by the lints of the clap command, other than invalid
+        // identifiers.
+        let is_linted = diagnostics[linted..]
+            .iter()
+            .any(|diagnostic| diagnostic.code != "invalid-identifier");
+        if is_valid && !is_linted {
+            match catch_unwind(AssertUnwindSafe(|| clap_cmd.clone().debug_assert())) {
+                Ok(()) => {
+                    let mut clap_cmd = clap_cmd;
+                    clap_cmd.build();
+                    let names = crate::var_prefix(&settings).and_then(|prefix| {
+                        crate::check_var_names(&clap_cmd, &settings, &composite_parts, &prefix)
+                    });
+                    if let Err(message) = names {
+                        diagnostics.push(Diagnostic::new("invalid-var-name", message, None));
+                    }
+                }
+                Err(err) => {
+                    let message = if let Some(message) = err.downcast_ref::<String>() {
+                        message
+                    } else if let Some(message) = err.downcast_ref::<&str>() {
+                        message
+                    } else {
+                        "clap debug assert failed"
+                    };
+                    diagnostics.push(Diagnostic::new("debug-assert", message.to_string(), None));
+                }
+            }
+        }
+        diagnostics
+    }
+}
+
+/// Lint for args without any help when `help-expected` is set on the command or a parent.
+///
+/// The `path` is the names of the command and its parents, outermost first.
+fn lint_help(cmd: &Command, help_expected: bool, path: &[&str], diagnostics: &mut Vec<Diagnostic>) {
+    let help_expected = help_expected || cmd.help_expected.unwrap_or_default();
+    if help_expected {
+        for (id, _) in cmd
+            .args
+            .iter()
+            .filter(|(_, arg)| arg.help.is_none() && arg.long_help.is_none())
+        {
+            diagnostics.push(Diagnostic::new(
+                "missing-help",
+                format!(
+                    "arg `{id}` of command `{}` has no `help` but `help-expected` is set",
+                    path.join(" ")
+                ),
+                None,
+            ));
+        }
+    }
+    for (name, subcommand) in cmd.subcommands.iter().flatten() {
+        let sub_path = [path, &[subcommand.name.as_deref().unwrap_or(name)]].concat();
+        lint_help(subcommand, help_expected, &sub_path, diagnostics);
+    }
+}
+
+/// Lint for positionals whose `index` is out of range or the same as another positional.
+///
+/// The `path` is the names of the command and its parents, outermost first.
+fn lint_indices(cmd: &Command, path: &[&str], diagnostics: &mut Vec<Diagnostic>) {
+    for (err, span) in cmd.index_errors() {
+        let err = path
+            .iter()
+            .rev()
+            .fold(err, |err, name| err.in_command(name));
+        diagnostics.push(Diagnostic::new(
+            "positional-index",
+            err.to_string(),
+            Some(span),
+        ));
+    }
+    for (name, subcommand) in cmd.subcommands.iter().flatten() {
+        let sub_path = [path, &[subcommand.name.as_deref().unwrap_or(name)]].concat();
+        lint_indices(subcommand, &sub_path, diagnostics);
+    }
+}
+
+/// Lint the args of a command and its subcommands.
+///
+/// The `version` flag is true if a parent command propagates its version to `cmd`.
+fn lint_command(
+    cmd: &clap::Command,
+    settings: &crate::Settings,
+    version: bool,
+    path: &[&str],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let path = [path, &[cmd.get_name()]].concat();
+    let command = path.join(" ");
+    let has_version = version || cmd.get_version().is_some() || cmd.get_long_version().is_some();
+    for arg in cmd.get_arguments() {
+        let id = arg.get_id().as_str();
+        if !id.chars().all(is_ident_char) {
+            diagnostics.push(Diagnostic::new(
+                "invalid-identifier",
+                format!(
+                    "id `{id}` of command `{command}` is not a valid shell identifier and is output as `{}`",
+                    settings.var_naming.apply(id)
+                ),
+                None,
+            ));
+        }
+        let builtin = match arg.get_short() {
+            Some('h') if !cmd.is_disable_help_flag_set() => Some("help"),
+            Some('V') if has_version && !cmd.is_disable_version_flag_set() => Some("version"),
+            _ => None,
+        };
+        if let (Some(builtin), Some(short)) = (builtin, arg.get_short()) {
+            diagnostics.push(Diagnostic::new(
+                "short-collision",
+                format!(
+                    "short `-{short}` of arg `{id}` of command `{command}` collides with the built-in {builtin} flag"
+                ),
+                None,
+            ));
+        }
+    }
+    lint_duplicate_flags(cmd, &command, diagnostics);
+    lint_positionals(cmd, &command, diagnostics);
+    lint_required_positionals(cmd, &command, diagnostics);
+    lint_last_positionals(cmd, &command, diagnostics);
+    let version = has_version && cmd.is_propagate_version_set();
+    for subcommand in cmd.get_subcommands() {
+        lint_command(subcommand, settings, version, &path, diagnostics);
+    }
+}
+
+/// Lint for shorts and longs which are used by more than one arg of a command.
+///
+/// Clap only reports these in its debug asserts, which are not run in release builds.
+fn lint_duplicate_flags(cmd: &clap::Command, command: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let shorts = cmd.get_arguments().flat_map(|arg| {
+        let shorts = arg
+            .get_short()
+            .into_iter()
+            .chain(arg.get_all_short_aliases().into_iter().flatten());
+        shorts.map(move |short| (format!("-{short}"), arg.get_id()))
+    });
+    let longs = cmd.get_arguments().flat_map(|arg| {
+        let longs = arg
+            .get_long()
+            .into_iter()
+            .chain(arg.get_all_aliases().into_iter().flatten());
+        longs.map(move |long| (format!("--{long}"), arg.get_id()))
+    });
+    let mut flags = IndexMap::<_, Vec<_>>::new();
+    for (flag, id) in shorts.chain(longs) {
+        let ids = flags.entry(flag).or_default();
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+    for (flag, ids) in flags {
+        if let [first, second, ..] = ids.as_slice() {
+            diagnostics.push(Diagnostic::new(
+                "duplicate-flag",
+                format!(
+                    "`{flag}` of command `{command}` is used by both arg `{first}` and arg `{second}`"
+                ),
+                None,
+            ));
+        }
+    }
+}
+
+/// Lint for positionals which can never be given a value as an earlier positional accepts
+/// an unlimited number of values.
+///
+/// Clap allows the final positional to follow such a positional if it is `required` or
+/// `last`, all other positionals are unreachable.
+fn lint_positionals(cmd: &clap::Command, command: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let positionals = positionals(cmd);
+    let Some(greedy) = positionals.iter().position(|arg| {
+        let unlimited = match arg.get_num_args() {
+            Some(range) => range.max_values() == usize::MAX,
+            None => matches!(arg.get_action(), ArgAction::Append),
+        };
+        unlimited && arg.get_value_terminator().is_none() && !arg.is_last_set()
+    }) else {
+        return;
+    };
+    let Some((last, rest)) = positionals[greedy + 1..].split_last() else {
+        return;
+    };
+    let unreachable = rest
+        .iter()
+        .chain((!last.is_required_set() && !last.is_last_set()).then_some(last));
+    for arg in unreachable {
+        diagnostics.push(Diagnostic::new(
+            "unreachable-positional",
+            format!(
+                "positional `{}` of command `{command}` is unreachable as positional `{}` accepts unlimited values",
+                arg.get_id(),
+                positionals[greedy].get_id()
+            ),
+            None,
+        ));
+    }
+}
+
+/// Lint for required positionals which follow an optional positional.
+///
+/// Clap only reports these in its debug asserts, unless the required positional is `last` or
+/// the command allows missing positionals.
+fn lint_required_positionals(
+    cmd: &clap::Command,
+    command: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if cmd.is_allow_missing_positional_set() {
+        return;
+    }
+    let positionals = positionals(cmd);
+    let Some(optional) = positionals.iter().find(|arg| !arg.is_required_set()) else {
+        return;
+    };
+    let required = positionals
+        .iter()
+        .skip_while(|arg| arg.get_id() != optional.get_id())
+        .filter(|arg| arg.is_required_set() && !arg.is_last_set());
+    for arg in required {
+        diagnostics.push(Diagnostic::new(
+            "required-positional",
+            format!(
+                "positional `{}` of command `{command}` is required but follows optional positional `{}` and is not `last`",
+                arg.get_id(),
+                optional.get_id()
+            ),
+            None,
+        ));
+    }
+}
+
+/// Lint for `last` positionals which clap only reports in its debug asserts.
+///
+/// A command may have only one `last` positional, which may only be required with
+/// subcommands if they negate the requirements of the command.
+fn lint_last_positionals(cmd: &clap::Command, command: &str, diagnostics: &mut Vec<Diagnostic>) {
+    let last = positionals(cmd)
+        .into_iter()
+        .filter(|arg| arg.is_last_set())
+        .collect::<Vec<_>>();
+    if let [first, second, ..] = last.as_slice() {
+        diagnostics.push(Diagnostic::new(
+            "last-positional",
+            format!(
+                "positionals `{}` and `{}` of command `{command}` are both `last`",
+                first.get_id(),
+                second.get_id()
+            ),
+            None,
+        ));
+    }
+    let subcommands = cmd.has_subcommands() && !cmd.is_subcommand_negates_reqs_set();
+    for arg in last
+        .iter()
+        .filter(|arg| subcommands && arg.is_required_set())
+    {
+        diagnostics.push(Diagnostic::new(
+            "last-positional",
+            format!(
+                "positional `{}` of command `{command}` is required and `last` but the command has subcommands without `subcommand-negates-reqs`",
+                arg.get_id()
+            ),
+            None,
+        ));
+    }
+}
+
+/// The positionals of a command in the order of their index.
+///
+/// Positionals without an `index` are numbered from 1 in the order in which they are
+/// declared, as clap does when the command is built.
+fn positionals(cmd: &clap::Command) -> Vec<&clap::Arg> {
+    let mut next = 0;
+    cmd.get_positionals()
+        .map(|arg| {
+            let index = arg.get_index().unwrap_or_else(|| {
+                next += 1;
+                next
+            });
+            (index, arg)
+        })
+        .sorted_by_key(|(index, _)| *index)
+        .map(|(_, arg)| arg)
+        .collect()
+}
+
+/// A single problem in the report of `claptrap check`.
+#[derive(Serialize)]
+struct Problem<'a> {
+    code: &'a str,
+    message: &'a str,
+    line: Option<usize>,
+    column: Option<usize>,
+}
+
+/// Render the result of a check such that it can be consumed by CI tools.
+///
+/// Problems are rendered as JSON when the format is `json` and otherwise as one line per
+/// problem, in the form `path:line:column: code: message`.
+pub fn render_check(
+    diagnostics: &[Diagnostic],
+    source: SpecSource<'_>,
+    format: OutputFormat,
+) -> String {
+    let problems = diagnostics.iter().map(|diagnostic| {
+        let location = diagnostic.location(source.text);
+        Problem {
+            code: diagnostic.code,
+            message: &diagnostic.message,
+            line: location.map(|(line, _)| line),
+            column: location.map(|(_, column)| column),
+        }
+    });
+    match format {
+        OutputFormat::Json => {
+            let report = serde_json::json!({
+                "path": source.path,
+                "problems": problems.collect::<Vec<_>>(),
+            });
+            format!("{report}\n")
+        }
+        _ => problems
+            .map(|problem| {
+                let location = match (problem.line, problem.column) {
+                    (Some(line), Some(column)) => format!("{}:{line}:{column}", source.path),
+                    _ => source.path.to_string(),
+                };
+                // Messages are kept to a single line so that each problem is one line.
+                let message = problem.message.lines().join(" ");
+                format!("{location}: {}: {message}\n", problem.code)
+            })
+            .collect(),
+    }
+}
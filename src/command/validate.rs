@@ -0,0 +1,273 @@
+use crate::command::Command;
+use clap::builder::StyledStr;
+use itertools::Itertools;
+use std::fmt::Write;
+use std::ops::Range;
+use toml::Spanned;
+
+/// A problem found in a spec, with the location of the offending value where known.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// A short, stable code identifying the kind of problem, for example `unknown-id`.
+    pub code: &'static str,
+    pub message: String,
+    /// The byte range of the offending value in the spec source.
+    pub span: Option<Range<usize>>,
+}
+
+impl Diagnostic {
+    pub(super) fn new(code: &'static str, message: String, span: Option<Range<usize>>) -> Self {
+        Self {
+            code,
+            message,
+            span,
+        }
+    }
+
+    /// The 1-based line and column of the start of the span in the spec source.
+    pub fn location(&self, text: &str) -> Option<(usize, usize)> {
+        let snippet = Snippet::new(text, self.span.as_ref()?);
+        Some((snippet.line, snippet.column))
+    }
+}
+
+impl From<toml::de::Error> for Diagnostic {
+    fn from(err: toml::de::Error) -> Self {
+        Self::new(
+            "invalid-toml",
+            err.message().trim_end().to_string(),
+            err.span(),
+        )
+    }
+}
+
+/// The source of a spec, used to locate diagnostics.
+#[derive(Debug, Clone, Copy)]
+pub struct SpecSource<'a> {
+    /// The path of the spec, as shown to the user.
+    pub path: &'a str,
+    pub text: &'a str,
+}
+
+impl Command {
+    /// Check that the root command is named and that every id referenced in the spec refers to
+    /// a known arg or group.
+    ///
+    /// All problems are returned, rather than only the first.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        // Subcommands are named after their table but the root command has no default name.
+        // The root table has no span, so the problem is located at the start of the spec.
+        if self.name.is_none() {
+            diagnostics.push(Diagnostic::new(
+                "missing-name",
+                String::from("the root command has no `name`"),
+                Some(0..0),
+            ));
+        }
+        validate_command(self, &[], &mut diagnostics);
+        diagnostics
+    }
+}
+
+/// Validate the references in `cmd`, where `globals` are the ids of global args declared
+/// by any parent command.
+fn validate_command(cmd: &Command, globals: &[String], diagnostics: &mut Vec<Diagnostic>) {
+    let arg_ids = cmd
+        .args
+        .iter()
+        .map(|(name, arg)| arg.id.clone().unwrap_or_else(|| name.clone()))
+        .chain(globals.iter().cloned())
+        .collect::<Vec<_>>();
+    let group_ids = cmd
+        .groups
+        .iter()
+        .flat_map(|groups| groups.keys().cloned())
+        .chain(
+            cmd.args
+                .values()
+                .flat_map(|arg| arg.group.iter().chain(arg.groups.iter().flatten()).cloned()),
+        )
+        .collect::<Vec<_>>();
+    let is_arg = |id: &str| arg_ids.iter().any(|arg_id| arg_id == id);
+    let is_arg_or_group = |id: &str| is_arg(id) || group_ids.iter().any(|group_id| group_id == id);
+
+    for (name, arg) in &cmd.args {
+        let references = [
+            ("requires", one(&arg.requires)),
+            ("required-unless-present", one(&arg.required_unless_present)),
+            (
+                "required-unless-present-all",
+                many(&arg.required_unless_present_all),
+            ),
+            (
+                "required-unless-present-any",
+                many(&arg.required_unless_present_any),
+            ),
+            ("conflicts-with", one(&arg.conflicts_with)),
+            ("conflicts-with-all", many(&arg.conflicts_with_all)),
+            ("overrides-with", one(&arg.overrides_with)),
+            ("overrides-with-all", many(&arg.overrides_with_all)),
+            (
+                "default-value-if",
+                Box::new(arg.default_value_if.iter().map(|rule| &rule.arg)),
+            ),
+            (
+                "default-value-ifs",
+                Box::new(arg.default_value_ifs.iter().flatten().map(|rule| &rule.arg)),
+            ),
+            (
+                "required-if-eq",
+                Box::new(arg.required_if_eq.iter().map(|rule| &rule.arg)),
+            ),
+            (
+                "required-if-eq-any",
+                Box::new(
+                    arg.required_if_eq_any
+                        .iter()
+                        .flatten()
+                        .map(|rule| &rule.arg),
+                ),
+            ),
+            (
+                "required-if-eq-all",
+                Box::new(
+                    arg.required_if_eq_all
+                        .iter()
+                        .flatten()
+                        .map(|rule| &rule.arg),
+                ),
+            ),
+            (
+                "requires-if",
+                Box::new(arg.requires_if.iter().map(|rule| &rule.arg)),
+            ),
+            (
+                "requires-ifs",
+                Box::new(arg.requires_ifs.iter().flatten().map(|rule| &rule.arg)),
+            ),
+        ];
+        for (key, ids) in references {
+            for id in ids.filter(|id| !is_arg_or_group(id.get_ref())) {
+                diagnostics.push(Diagnostic::new(
+                    "unknown-id",
+                    format!(
+                        "`{key}` of arg `{name}` refers to unknown arg or group `{}`",
+                        id.get_ref()
+                    ),
+                    Some(id.span()),
+                ));
+            }
+        }
+    }
+    for (name, group) in cmd.groups.iter().flatten() {
+        for id in many(&group.args).filter(|id| !is_arg(id.get_ref())) {
+            diagnostics.push(Diagnostic::new(
+                "unknown-id",
+                format!(
+                    "`args` of group `{name}` refers to unknown arg `{}`",
+                    id.get_ref()
+                ),
+                Some(id.span()),
+            ));
+        }
+        let references = [
+            ("requires", one(&group.requires)),
+            ("requires-all", many(&group.requires_all)),
+            ("conflicts-with", one(&group.conflicts_with)),
+            ("conflicts-with-all", many(&group.conflicts_with_all)),
+        ];
+        for (key, ids) in references {
+            for id in ids.filter(|id| !is_arg_or_group(id.get_ref())) {
+                diagnostics.push(Diagnostic::new(
+                    "unknown-id",
+                    format!(
+                        "`{key}` of group `{name}` refers to unknown arg or group `{}`",
+                        id.get_ref()
+                    ),
+                    Some(id.span()),
+                ));
+            }
+        }
+    }
+
+    let sub_globals = cmd
+        .args
+        .iter()
+        .filter(|(_, arg)| arg.global.unwrap_or_default())
+        .map(|(name, arg)| arg.id.clone().unwrap_or_else(|| name.clone()))
+        .chain(globals.iter().cloned())
+        .collect::<Vec<_>>();
+    for subcommand in cmd.subcommands.iter().flat_map(|subs| subs.values()) {
+        validate_command(subcommand, &sub_globals, diagnostics);
+    }
+}
+
+type References<'a> = Box<dyn Iterator<Item = &'a Spanned<String>> + 'a>;
+
+fn one(id: &Option<Spanned<String>>) -> References<'_> {
+    Box::new(id.iter())
+}
+
+fn many(ids: &Option<Vec<Spanned<String>>>) -> References<'_> {
+    Box::new(ids.iter().flatten())
+}
+
+/// Render diagnostics in the style of a clap error.
+///
+/// When the spec source is provided, each diagnostic with a span is followed by the path,
+/// line and column of the offending value and a snippet of the source line.
+pub fn render_diagnostics(diagnostics: &[Diagnostic], source: Option<SpecSource<'_>>) -> StyledStr {
+    let error = *clap::builder::Styles::styled().get_error();
+    let mut styled = StyledStr::new();
+    let rendered = diagnostics.iter().map(|diagnostic| {
+        let mut rendered = format!("{error}error:{error:#} {}\n", diagnostic.message);
+        if let (Some(source), Some(span)) = (source, &diagnostic.span) {
+            let snippet = Snippet::new(source.text, span);
+            let gutter = " ".repeat(snippet.line.to_string().len());
+            let _ = writeln!(
+                rendered,
+                "{gutter}--> {}:{}:{}",
+                source.path, snippet.line, snippet.column
+            );
+            let _ = writeln!(rendered, "{gutter} |");
+            let _ = writeln!(rendered, "{} | {}", snippet.line, snippet.text);
+            let _ = writeln!(
+                rendered,
+                "{gutter} | {}{error}{}{error:#}",
+                " ".repeat(snippet.column - 1),
+                "^".repeat(snippet.width)
+            );
+        }
+        rendered
+    });
+    let _ = write!(styled, "{}", rendered.format("\n"));
+    styled
+}
+
+/// The source line containing a span, with the 1-based line and column of its start.
+struct Snippet<'a> {
+    line: usize,
+    column: usize,
+    /// The width of the span in chars, limited to the end of the line.
+    width: usize,
+    text: &'a str,
+}
+
+impl<'a> Snippet<'a> {
+    fn new(source: &'a str, span: &Range<usize>) -> Self {
+        let start = span.start.min(source.len());
+        let line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[start..]
+            .find('\n')
+            .map_or(source.len(), |i| start + i);
+        let text = source[line_start..line_end].trim_end_matches('\r');
+        let end = span.end.clamp(start, line_end);
+        Self {
+            line: source[..start].matches('\n').count() + 1,
+            column: source[line_start..start].chars().count() + 1,
+            width: source[start..end].chars().count().max(1),
+            text,
+        }
+    }
+}
@@ -0,0 +1,469 @@
+use crate::command::{PathKind, PathSpec};
+use clap::builder::{PathBufValueParser, StringValueParser, TypedValueParser};
+use regex::Regex;
+use std::ffi::OsStr;
+use std::fmt::Display;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+
+/// A value parser which only accepts values matching a regex, from `value-pattern`.
+///
+/// The regex is not anchored, use `^` and `$` to match the whole value.
+#[derive(Clone, Debug)]
+pub(super) struct PatternValueParser(Regex);
+
+impl PatternValueParser {
+    pub(super) fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Regex::new(pattern).map(Self)
+    }
+}
+
+impl TypedValueParser for PatternValueParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let pattern = self.0.clone();
+        validate(move |value| {
+            pattern
+                .is_match(value)
+                .then_some(())
+                .ok_or_else(|| format!("does not match the pattern `{pattern}`"))
+        })
+        .parse_ref(cmd, arg, value)
+    }
+}
+
+/// A value parser which only accepts values matching a glob, from `value-glob`.
+///
+/// A `*` also matches `/`, such that `release/*` matches `release/1.0/rc1`.
+#[derive(Clone, Debug)]
+pub(super) struct GlobValueParser(glob::Pattern);
+
+impl GlobValueParser {
+    pub(super) fn new(glob: &str) -> Result<Self, glob::PatternError> {
+        glob::Pattern::new(glob).map(Self)
+    }
+}
+
+impl TypedValueParser for GlobValueParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let glob = self.0.clone();
+        validate(move |value| {
+            glob.matches(value)
+                .then_some(())
+                .ok_or_else(|| format!("does not match the glob `{glob}`"))
+        })
+        .parse_ref(cmd, arg, value)
+    }
+}
+
+/// Build a parser from a check of a string value.
+///
+/// Clap implements `TypedValueParser` for such functions, reporting a failed check as a
+/// `ValueValidation` error for the arg, including the reason, and any value which is not
+/// UTF-8 as an `InvalidUtf8` error.
+fn validate<F>(check: F) -> impl Fn(&str) -> Result<String, String> + Clone + Send + Sync + 'static
+where
+    F: Fn(&str) -> Result<(), String> + Clone + Send + Sync + 'static,
+{
+    move |value| check(value).map(|()| value.to_string())
+}
+
+/// A check of a string value, from either `value-pattern` or `value-glob`.
+#[derive(Clone, Debug)]
+pub(super) enum ValueCheck {
+    Pattern(PatternValueParser),
+    Glob(GlobValueParser),
+}
+
+impl TypedValueParser for ValueCheck {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        match self {
+            ValueCheck::Pattern(parser) => parser.parse_ref(cmd, arg, value),
+            ValueCheck::Glob(parser) => parser.parse_ref(cmd, arg, value),
+        }
+    }
+}
+
+/// A value parser which checks a path against the filesystem, from `path`.
+///
+/// Any `value-pattern` or `value-glob` is checked first, in which case the path must be valid
+/// UTF-8.
+#[derive(Clone, Debug)]
+pub(super) struct PathValueParser(PathSpec, Option<ValueCheck>);
+
+impl PathValueParser {
+    pub(super) fn new(spec: PathSpec, check: Option<ValueCheck>) -> Self {
+        Self(spec, check)
+    }
+}
+
+impl TypedValueParser for PathValueParser {
+    type Value = PathBuf;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        if let Some(check) = &self.1 {
+            check.parse_ref(cmd, arg, value)?;
+        }
+        let spec = self.0.clone();
+        PathBufValueParser::new()
+            .try_map(move |path| check_path(&spec, path))
+            .parse_ref(cmd, arg, value)
+    }
+}
+
+/// Check a path against the spec, returning the path to output or the reason it is invalid.
+fn check_path(spec: &PathSpec, path: PathBuf) -> Result<PathBuf, String> {
+    let exists = path.try_exists().map_err(|err| err.to_string())?;
+    if !exists {
+        let must_exist = spec.must_exist.unwrap_or_default()
+            || spec.readable.unwrap_or_default()
+            || spec.executable.unwrap_or_default();
+        if must_exist {
+            return Err(String::from("does not exist"));
+        }
+        // A path which does not exist yet is writable if it can be created.
+        if spec.writable.unwrap_or_default() {
+            let parent = match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => parent,
+                _ => Path::new("."),
+            };
+            if !parent.is_dir() || !has_access(parent, Access::Write) {
+                return Err(String::from("cannot be created"));
+            }
+        }
+    } else {
+        match spec.kind {
+            Some(PathKind::File) if !path.is_file() => return Err(String::from("is not a file")),
+            Some(PathKind::Dir) if !path.is_dir() => {
+                return Err(String::from("is not a directory"));
+            }
+            _ => {}
+        }
+        let checks = [
+            (spec.readable, Access::Read, "is not readable"),
+            (spec.writable, Access::Write, "is not writable"),
+            (spec.executable, Access::Execute, "is not executable"),
+        ];
+        for (enabled, access, reason) in checks {
+            if enabled.unwrap_or_default() && !has_access(&path, access) {
+                return Err(String::from(reason));
+            }
+        }
+    }
+    if !spec.canonicalize.unwrap_or_default() {
+        Ok(path)
+    } else if exists {
+        path.canonicalize().map_err(|err| err.to_string())
+    } else {
+        std::path::absolute(&path).map_err(|err| err.to_string())
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+/// Check whether the current user has the access to the path, as per `access(2)`.
+#[cfg(unix)]
+fn has_access(path: &Path, access: Access) -> bool {
+    let access = match access {
+        Access::Read => rustix::fs::Access::READ_OK,
+        Access::Write => rustix::fs::Access::WRITE_OK,
+        Access::Execute => rustix::fs::Access::EXEC_OK,
+    };
+    rustix::fs::access(path, access).is_ok()
+}
+
+/// Check whether the path has the access, approximated from its metadata.
+#[cfg(not(unix))]
+fn has_access(path: &Path, access: Access) -> bool {
+    match (access, path.metadata()) {
+        (_, Err(_)) => false,
+        (Access::Write, Ok(metadata)) => !metadata.permissions().readonly(),
+        (Access::Read | Access::Execute, Ok(_)) => true,
+    }
+}
+
+/// A value which is output along with named parts, for example a duration and the same
+/// duration in milliseconds.
+///
+/// Each part is output as a separate variable, named after the arg id and the part.  Every
+/// value produced by a parser has the same parts, in the same order.
+#[derive(Clone, Debug)]
+pub(crate) struct CompositeValue {
+    /// The value as given.
+    pub(crate) value: String,
+    pub(crate) parts: Vec<(&'static str, String)>,
+}
+
+impl Display for CompositeValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.value)
+    }
+}
+
+/// The named parts of a value, or the reason the value is invalid.
+type Parts = Result<Vec<(&'static str, String)>, String>;
+
+/// A value parser which produces a `CompositeValue`, with the names of its parts and the parts
+/// of the value from a parse function.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct CompositeValueParser(&'static [&'static str], fn(&str) -> Parts);
+
+impl CompositeValueParser {
+    /// The names of the parts of every value, in order.
+    pub(super) fn parts(&self) -> &'static [&'static str] {
+        self.0
+    }
+
+    /// A duration such as `30s`, `1.5h` or `1h30m`, with the part `ms`.
+    pub(super) fn duration() -> Self {
+        Self(&["ms"], |value| {
+            Ok(vec![("ms", parse_duration(value)?.to_string())])
+        })
+    }
+
+    /// A size in bytes such as `512`, `10KB` or `2GiB`, with the part `bytes`.
+    pub(super) fn bytes() -> Self {
+        Self(&["bytes"], |value| {
+            Ok(vec![("bytes", parse_bytes(value)?.to_string())])
+        })
+    }
+
+    /// An IP network such as `10.0.0.0/8`, with the parts `address`, `prefix` and `network`.
+    pub(super) fn cidr() -> Self {
+        Self(&["address", "prefix", "network"], cidr_parts)
+    }
+
+    /// An IP address and port such as `127.0.0.1:80` or `[::1]:80`, with the parts `host`
+    /// and `port`.
+    pub(super) fn socket_addr() -> Self {
+        Self(&["host", "port"], |value| {
+            let addr = value.parse::<SocketAddr>().map_err(|err| err.to_string())?;
+            Ok(vec![
+                ("host", addr.ip().to_string()),
+                ("port", addr.port().to_string()),
+            ])
+        })
+    }
+
+    /// An absolute URL, with the parts `scheme`, `host`, `port`, `path`, `query` and
+    /// `fragment`.
+    ///
+    /// The `port` is the default port of the scheme, if known, when the URL has no port.  Any
+    /// missing part is empty.
+    pub(super) fn url() -> Self {
+        const PARTS: &[&str] = &["scheme", "host", "port", "path", "query", "fragment"];
+        Self(PARTS, |value| {
+            let url = url::Url::parse(value).map_err(|err| err.to_string())?;
+            Ok(vec![
+                ("scheme", url.scheme().to_string()),
+                ("host", url.host_str().unwrap_or_default().to_string()),
+                (
+                    "port",
+                    url.port_or_known_default()
+                        .map(|port| port.to_string())
+                        .unwrap_or_default(),
+                ),
+                ("path", url.path().to_string()),
+                ("query", url.query().unwrap_or_default().to_string()),
+                ("fragment", url.fragment().unwrap_or_default().to_string()),
+            ])
+        })
+    }
+}
+
+impl TypedValueParser for CompositeValueParser {
+    type Value = CompositeValue;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        let parse = self.1;
+        StringValueParser::new()
+            .try_map(move |value| {
+                let parts = parse(&value)?;
+                Ok::<_, String>(CompositeValue { value, parts })
+            })
+            .parse_ref(cmd, arg, value)
+    }
+}
+
+/// A value parser for a hostname, which checks the syntax of RFC 1123 without any lookup.
+///
+/// A hostname is up to 253 characters, excluding an optional trailing `.`, and consists of
+/// labels of up to 63 letters, digits or hyphens, which do not start or end with a hyphen.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct HostnameValueParser;
+
+impl TypedValueParser for HostnameValueParser {
+    type Value = String;
+
+    fn parse_ref(
+        &self,
+        cmd: &clap::Command,
+        arg: Option<&clap::Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, clap::Error> {
+        StringValueParser::new()
+            .try_map(|value| {
+                let name = value.strip_suffix('.').unwrap_or(&value);
+                let is_label = |label: &str| {
+                    (1..=63).contains(&label.len())
+                        && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                        && !label.starts_with('-')
+                        && !label.ends_with('-')
+                };
+                if name.len() <= 253 && name.split('.').all(is_label) {
+                    Ok(value)
+                } else {
+                    Err("invalid hostname syntax")
+                }
+            })
+            .parse_ref(cmd, arg, value)
+    }
+}
+
+/// The parts of an IP network, where the `network` is the address with any host bits
+/// cleared.
+fn cidr_parts(value: &str) -> Parts {
+    const EXPECTED: &str = "expected an IP network such as `10.0.0.0/8` or `fd00::/8`";
+    let (address, prefix) = value.split_once('/').ok_or(EXPECTED)?;
+    let address = address.parse::<IpAddr>().map_err(|err| err.to_string())?;
+    if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_digit()) {
+        return Err(String::from(EXPECTED));
+    }
+    let prefix = prefix.parse::<u32>().map_err(|_| EXPECTED)?;
+    let network = match address {
+        IpAddr::V4(addr) if prefix <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            IpAddr::V4(Ipv4Addr::from(u32::from(addr) & mask))
+        }
+        IpAddr::V6(addr) if prefix <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            IpAddr::V6(Ipv6Addr::from(u128::from(addr) & mask))
+        }
+        _ => return Err(format!("prefix length {prefix} is too long for {address}")),
+    };
+    Ok(vec![
+        ("address", address.to_string()),
+        ("prefix", prefix.to_string()),
+        ("network", format!("{network}/{prefix}")),
+    ])
+}
+
+/// Parse a duration into milliseconds.
+///
+/// A duration is one or more numbers, each followed by one of the units `ms`, `s`, `m`, `h`
+/// or `d`.  Any fraction of a millisecond is discarded.
+fn parse_duration(value: &str) -> Result<u64, String> {
+    const EXPECTED: &str = "expected a number and unit such as `500ms`, `30s`, `5m` or `1h30m`";
+    if value.is_empty() {
+        return Err(String::from(EXPECTED));
+    }
+    let mut millis: u128 = 0;
+    let mut rest = value;
+    while !rest.is_empty() {
+        let (number, tail) = split_number(rest);
+        let unit_len = tail
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+        let multiplier = match unit {
+            "ms" => 1,
+            "s" => 1_000,
+            "m" => 60_000,
+            "h" => 3_600_000,
+            "d" => 86_400_000,
+            _ => return Err(String::from(EXPECTED)),
+        };
+        let part = scale(number, multiplier).ok_or(EXPECTED)?;
+        millis = millis.checked_add(part).ok_or("duration is too large")?;
+        rest = tail;
+    }
+    u64::try_from(millis).map_err(|_| String::from("duration is too large"))
+}
+
+/// Parse a size into bytes.
+///
+/// A size is a number followed by an optional unit, which is case-insensitive.  The units
+/// `k`, `m`, `g`, `t` and `p` (optionally followed by `b`) are powers of 1000 and the units
+/// `ki`, `mi`, `gi`, `ti` and `pi` (optionally followed by `b`) are powers of 1024.  Any
+/// fraction of a byte is discarded.
+fn parse_bytes(value: &str) -> Result<u64, String> {
+    const EXPECTED: &str = "expected a number and optional unit such as `512`, `10KB` or `2GiB`";
+    let (number, unit) = split_number(value);
+    let unit = unit.to_ascii_lowercase();
+    let unit = unit.strip_suffix('b').unwrap_or(&unit);
+    let (prefix, base) = match unit.strip_suffix('i') {
+        Some(prefix) if !prefix.is_empty() => (prefix, 1024u128),
+        _ => (unit, 1000u128),
+    };
+    let exponent = match prefix {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        "p" => 5,
+        _ => return Err(String::from(EXPECTED)),
+    };
+    let bytes = scale(number, base.pow(exponent)).ok_or(EXPECTED)?;
+    u64::try_from(bytes).map_err(|_| String::from("size is too large"))
+}
+
+/// Split a leading decimal number, such as `1` or `1.5`, from the rest of a value.
+fn split_number(value: &str) -> (&str, &str) {
+    let len = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    value.split_at(len)
+}
+
+/// Multiply a decimal number by an integer, discarding any fraction of the result.
+///
+/// Returns `None` if the number is not valid or the result overflows.
+fn scale(number: &str, multiplier: u128) -> Option<u128> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let whole = whole.parse::<u128>().ok()?.checked_mul(multiplier)?;
+    if fraction.is_empty() {
+        return Some(whole);
+    }
+    let denominator = 10u128.checked_pow(u32::try_from(fraction.len()).ok()?)?;
+    let fraction = fraction.parse::<u128>().ok()?.checked_mul(multiplier)? / denominator;
+    whole.checked_add(fraction)
+}
@@ -1,6 +1,6 @@
 use crate::{CatCmd, Output};
 use clap::builder::StyledStr;
-use claptrap::output::ExitCode;
+use claptrap::output::{ExitCode, OutputFormat, Rendered};
 use std::fmt::{Display, Formatter};
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -9,7 +9,14 @@ pub type Result<T> = std::result::Result<T, Error>;
 ///
 /// This error type wraps an `Output` which is eval-safe.
 #[derive(Debug)]
-pub struct Error(Output);
+pub struct Error(Box<Output>);
+
+impl Error {
+    /// Render the error in the given format.
+    pub fn render(&self, format: OutputFormat) -> Rendered<'_> {
+        self.0.render(format)
+    }
+}
 
 impl std::error::Error for Error {}
 
@@ -21,18 +28,19 @@ impl Display for Error {
 
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
-        Error(Output::Cat(CatCmd::new(
+        Error(Box::new(Output::Cat(CatCmd::new(
             StyledStr::from(format!("{err}\n")),
             ExitCode::Error,
-        )))
+        ))))
     }
 }
 
-impl From<toml::de::Error> for Error {
-    fn from(err: toml::de::Error) -> Self {
-        Error(Output::Cat(CatCmd::new(
-            StyledStr::from(format!("{err}\n")),
+impl Error {
+    /// An invalid spec, described by the rendered diagnostics.
+    pub fn spec(diagnostics: StyledStr) -> Self {
+        Error(Box::new(Output::Cat(CatCmd::new(
+            diagnostics,
             ExitCode::Error,
-        )))
+        ))))
     }
 }
@@ -1,7 +1,17 @@
-use crate::command::Command;
-use crate::output::{CatCmd, ExitCode, Output, Var};
+use crate::command::{Command, CompositeParts, CompositeValue, render_diagnostics};
+use crate::output::{
+    CatCmd, ExitCode, Match, Matches, Output, OutputFormat, Var, VarNaming, Variables, full_name,
+};
 use clap::ArgAction;
-use std::ffi::OsString;
+use clap::builder::StyledStr;
+use clap::parser::ValueSource;
+use indexmap::IndexMap;
+use itertools::Itertools;
+use std::any::TypeId;
+use std::ffi::{OsStr, OsString};
+use std::fmt::{Display, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::PathBuf;
 
 pub mod command;
 pub mod output;
@@ -10,9 +20,64 @@ pub mod output;
 ///
 /// This function does not perform any I/O operations.
 pub fn parse(cmd: Command, args: Vec<OsString>) -> Output {
-    let clap_app = clap::Command::from(cmd).no_binary_name(true);
+    let settings = cmd.settings();
+    // A spec from `Command::from_toml` has already been validated, along with its source.
+    let diagnostics = if cmd.is_validated() {
+        vec![]
+    } else {
+        cmd.validate()
+    };
+    if !diagnostics.is_empty() {
+        return Output::Cat(CatCmd::new(
+            render_diagnostics(&diagnostics, None),
+            ExitCode::Error,
+        ));
+    }
+    let (mut clap_app, composite_parts) = match cmd.try_into_clap() {
+        Ok((clap_app, composite_parts)) => (clap_app.no_binary_name(true), composite_parts),
+        Err(err) => return error_output(&err),
+    };
+    // Without a binary name clap cannot build the full usage of subcommands, so
+    // default it to the name of the command.
+    if clap_app.get_bin_name().is_none() {
+        let bin_name = clap_app.get_name().to_string();
+        clap_app = clap_app.bin_name(bin_name);
+    }
+    // Build the command so that groups declared on args are available when extracting.
+    clap_app.build();
+    let prefix = match var_prefix(&settings) {
+        Ok(prefix) => prefix,
+        Err(message) => return error_output(&message),
+    };
+    if let Err(message) = check_var_names(&clap_app, &settings, &composite_parts, &prefix) {
+        return error_output(&message);
+    }
+    let naming = settings.var_naming;
     match clap_app.clone().try_get_matches_from(args) {
-        Ok(matches) => Output::Variables(extract_matches(clap_app, matches)),
+        Ok(matches) => {
+            let mut matched = Matches::default();
+            let mut vars = extract_matches(
+                &clap_app,
+                &matches,
+                &settings,
+                &composite_parts,
+                &[],
+                &mut matched,
+            );
+            if settings.assoc_array {
+                vars.push(assoc_array(&vars));
+                vars.push(Var::Many(
+                    var_name(naming, &[], PRESENT),
+                    present_ids(&clap_app, &matches, naming, &[]),
+                ));
+            }
+            Output::Variables(Variables::new(
+                prefix,
+                subcommand_path(&matches),
+                matched,
+                vars,
+            ))
+        }
         Err(err) => match err.kind() {
             clap::error::ErrorKind::DisplayHelp | clap::error::ErrorKind::DisplayVersion => {
                 Output::Cat(CatCmd::new(err.render(), ExitCode::Success))
@@ -25,47 +90,770 @@ pub fn parse(cmd: Command, args: Vec<OsString>) -> Output {
     }
 }
 
-// TODO: subcommand and groups
-fn extract_matches(cmd: clap::Command, matches: clap::ArgMatches) -> Vec<Var> {
-    matches
-        .ids()
-        .filter_map(|id| {
-            let arg = cmd.get_arguments().find(|a| a.get_id() == id)?;
-            match arg.get_action() {
-                ArgAction::SetTrue | ArgAction::SetFalse => Some(Var::Single(
-                    id.to_string(),
-                    matches.get_flag(id.as_str()).to_string(),
-                )),
-                ArgAction::Count => Some(Var::Single(
-                    id.to_string(),
-                    matches.get_count(id.as_str()).to_string(),
-                )),
-                ArgAction::Append => matches.get_many::<String>(id.as_str()).map(|values| {
-                    Var::Many(
-                        id.to_string(),
-                        values.into_iter().map(|value| value.to_owned()).collect(),
+fn extract_matches(
+    cmd: &clap::Command,
+    matches: &clap::ArgMatches,
+    settings: &Settings,
+    composite_parts: &CompositeParts,
+    path: &[&str],
+    matched: &mut Matches,
+) -> Vec<Var> {
+    let naming = settings.var_naming;
+    let mut vars = vec![];
+    for id in matches.ids() {
+        if let Some(group) = cmd.get_groups().find(|g| g.get_id() == id) {
+            if let Some(group) = match_group(group, matches, path) {
+                let name = var_name(naming, path, &group.id);
+                vars.extend(values_var(name, group.values(), group.multiple));
+                matched.groups.push(group);
+            }
+        } else if let Some(arg) = cmd.get_arguments().find(|a| a.get_id() == id) {
+            if let Some(arg) = match_arg(arg, matches, settings, composite_parts, path) {
+                vars.extend(arg_vars(&arg, naming, path));
+                matched.args.push(arg);
+            }
+        }
+    }
+    if settings.indices {
+        vars.push(Var::Many(
+            var_name(naming, path, ORDER),
+            arg_order(cmd, matches),
+        ));
+    }
+    if settings.declare_all {
+        vars.extend(absent_vars(cmd, &vars, settings, composite_parts, path));
+    }
+    if let Some((sub_name, sub_matches)) = matches.subcommand() {
+        vars.push(Var::Single(
+            var_name(naming, path, SUBCOMMAND),
+            sub_name.into(),
+        ));
+        if let Some(sub_cmd) = cmd.find_subcommand(sub_name) {
+            let sub_path = [path, &[sub_name]].concat();
+            vars.extend(extract_matches(
+                sub_cmd,
+                sub_matches,
+                settings,
+                composite_parts,
+                &sub_path,
+                matched,
+            ));
+        } else {
+            // An external subcommand, whose args are passed through as-is.
+            let args = sub_matches.get_raw("").into_iter().flatten();
+            let args = args.map(OsStr::to_os_string).collect::<Vec<_>>();
+            vars.push(Var::Many(
+                var_name(naming, path, EXTERNAL_ARGS),
+                args.clone(),
+            ));
+            matched.external_args = Some(args);
+        }
+    } else if settings.declare_all && has_subcommands(cmd) {
+        vars.push(Var::Single(
+            var_name(naming, path, SUBCOMMAND),
+            OsString::new(),
+        ));
+        if cmd.is_allow_external_subcommands_set() {
+            vars.push(Var::Many(var_name(naming, path, EXTERNAL_ARGS), vec![]));
+        }
+    }
+    vars
+}
+
+/// Match the value(s) of an arg, returning `None` if the arg has no values.
+fn match_arg(
+    arg: &clap::Arg,
+    matches: &clap::ArgMatches,
+    settings: &Settings,
+    composite_parts: &CompositeParts,
+    path: &[&str],
+) -> Option<Match> {
+    let id = arg.get_id().as_str();
+    let (multiple, occurrences, parts) = match arg.get_action() {
+        ArgAction::SetTrue | ArgAction::SetFalse => {
+            let value = matches.get_flag(id).to_string();
+            (false, vec![vec![value.into()]], vec![])
+        }
+        ArgAction::Count => {
+            let value = matches.get_count(id).to_string();
+            (false, vec![vec![value.into()]], vec![])
+        }
+        ArgAction::Append | ArgAction::Set => {
+            let multiple = matches!(arg.get_action(), ArgAction::Append) || arg.is_many();
+            match composite_parts.get(path, arg) {
+                Some(names) => {
+                    let (occurrences, parts) = composite_values(matches, id, names)?;
+                    (
+                        multiple,
+                        occurrences,
+                        names.iter().copied().zip(parts).collect(),
                     )
-                }),
-                ArgAction::Set => {
-                    if arg.is_many() {
-                        matches.get_many::<String>(id.as_str()).map(|values| {
-                            Var::Many(
-                                id.to_string(),
-                                values.into_iter().map(|value| value.to_owned()).collect(),
-                            )
-                        })
-                    } else {
-                        matches
-                            .get_one::<String>(id.as_str())
-                            .map(|value| Var::Single(id.to_string(), value.to_owned()))
-                    }
                 }
-                _ => None,
+                None => (multiple, typed_values(arg, matches)?, vec![]),
             }
+        }
+        _ => return None,
+    };
+    if !multiple && occurrences.iter().flatten().next().is_none() {
+        return None;
+    }
+    let source = settings
+        .value_source
+        .then(|| matches.value_source(id).and_then(source_name))
+        .flatten();
+    let indices = (settings.indices && is_cli(matches, id))
+        .then(|| matches.indices_of(id).into_iter().flatten().collect());
+    Some(Match {
+        path: path.iter().map(ToString::to_string).collect(),
+        id: id.to_string(),
+        multiple,
+        occurrences,
+        grouped: is_grouped(arg),
+        parts,
+        source,
+        indices,
+    })
+}
+
+/// The variables for a matched arg, along with any parts, source and indices.
+fn arg_vars(arg: &Match, naming: VarNaming, path: &[&str]) -> Vec<Var> {
+    let id = &arg.id;
+    let name = |suffix: &str| var_name(naming, path, &format!("{id}_{suffix}"));
+    let value = if arg.grouped {
+        Some(Var::Grouped(
+            var_name(naming, path, id),
+            name(OCCURRENCES),
+            arg.occurrences.clone(),
+        ))
+    } else {
+        values_var(var_name(naming, path, id), arg.values(), arg.multiple)
+    };
+    let parts = arg
+        .parts
+        .iter()
+        .filter_map(|(part, values)| values_var(name(part), values.clone(), arg.multiple));
+    let source = arg
+        .source
+        .map(|source| Var::Single(name(SOURCE), source.into()));
+    let indices = arg.indices.as_ref().map(|indices| {
+        Var::Many(
+            name(INDICES),
+            indices
+                .iter()
+                .map(|index| index.to_string().into())
+                .collect(),
+        )
+    });
+    value
+        .into_iter()
+        .chain(parts)
+        .chain(source)
+        .chain(indices)
+        .collect()
+}
+
+/// Empty variables for the args and groups of `cmd` which have not been output in `vars`.
+///
+/// Flags are `false` (or `true` for `set-false`) and counts are `0`.  Options and positionals
+/// are empty, or an empty array if many-valued, as are any of their parts and sources.  Indices
+/// are an empty array.
+fn absent_vars(
+    cmd: &clap::Command,
+    vars: &[Var],
+    settings: &Settings,
+    composite_parts: &CompositeParts,
+    path: &[&str],
+) -> Vec<Var> {
+    let naming = settings.var_naming;
+    let is_output = |name: &str| vars.iter().any(|var| var.name() == name);
+    let empty = |name: String, is_many: bool| {
+        if is_many {
+            Var::Many(name, vec![])
+        } else {
+            Var::Single(name, OsString::new())
+        }
+    };
+    let mut absent = vec![];
+    for arg in cmd.get_arguments() {
+        let id = arg.get_id().as_str();
+        let name = var_name(naming, path, id);
+        let value = match arg.get_action() {
+            ArgAction::SetTrue => Var::Single(name, false.to_string().into()),
+            ArgAction::SetFalse => Var::Single(name, true.to_string().into()),
+            ArgAction::Count => Var::Single(name, 0.to_string().into()),
+            ArgAction::Append if is_grouped(arg) => {
+                let count = var_name(naming, path, &format!("{id}_{OCCURRENCES}"));
+                Var::Grouped(name, count, vec![])
+            }
+            ArgAction::Set | ArgAction::Append => empty(
+                name,
+                matches!(arg.get_action(), ArgAction::Append) || arg.is_many(),
+            ),
+            _ => continue,
+        };
+        let is_many = matches!(value, Var::Many(..) | Var::Grouped(..));
+        let parts = composite_parts
+            .get(path, arg)
+            .unwrap_or_default()
+            .iter()
+            .map(|part| empty(var_name(naming, path, &format!("{id}_{part}")), is_many));
+        let source = settings.value_source.then(|| {
+            Var::Single(
+                var_name(naming, path, &format!("{id}_{SOURCE}")),
+                OsString::new(),
+            )
+        });
+        let indices = settings
+            .indices
+            .then(|| Var::Many(var_name(naming, path, &format!("{id}_{INDICES}")), vec![]));
+        absent.extend(
+            std::iter::once(value)
+                .chain(parts)
+                .chain(source)
+                .chain(indices)
+                .filter(|var| !is_output(var.name())),
+        );
+    }
+    for group in cmd.get_groups() {
+        let name = var_name(naming, path, group.get_id().as_str());
+        if !is_output(&name) {
+            absent.push(empty(name, group.clone().is_multiple()));
+        }
+    }
+    absent
+}
+
+/// Returns true if the value of an arg was given on the command line.
+fn is_cli(matches: &clap::ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+/// The ids of the args of `cmd` which were present on the command line, once for each
+/// occurrence, in the order in which they occurred.
+///
+/// The ids are output as-is, rather than as variable names, such that they can be compared with
+/// the ids in the spec.  An occurrence is ordered by the index of its first value, as reported by
+/// clap.  Clap only reports a single index for a `count` arg and so it is output once.
+fn arg_order(cmd: &clap::Command, matches: &clap::ArgMatches) -> Vec<OsString> {
+    cmd.get_arguments()
+        .map(|arg| arg.get_id().as_str())
+        .filter(|id| is_cli(matches, id))
+        .flat_map(|id| {
+            let mut indices = matches.indices_of(id).into_iter().flatten();
+            let occurrences = matches.get_raw_occurrences(id).into_iter().flatten();
+            occurrences.filter_map(move |values| {
+                let first = indices.next()?;
+                // Skip the indices of the remaining values of the occurrence.
+                indices
+                    .by_ref()
+                    .take(values.len().saturating_sub(1))
+                    .count();
+                Some((first, id))
+            })
         })
+        .sorted_by_key(|(index, _)| *index)
+        .map(|(_, id)| id.into())
         .collect()
 }
 
+/// The name of the source of a value, as output when `value-source` is set.
+fn source_name(source: ValueSource) -> Option<&'static str> {
+    match source {
+        ValueSource::CommandLine => Some("cli"),
+        ValueSource::EnvVariable => Some("env"),
+        ValueSource::DefaultValue => Some("default"),
+        _ => None,
+    }
+}
+
+/// Stringify the values of each occurrence of an arg from the type produced by its value
+/// parser.
+fn typed_values(arg: &clap::Arg, matches: &clap::ArgMatches) -> Option<Occurrences> {
+    let id = arg.get_id().as_str();
+    let type_id = arg.get_value_parser().type_id();
+    let stringifiers: [(TypeId, Stringifier); 16] = [
+        (TypeId::of::<bool>(), stringify::<bool>),
+        (TypeId::of::<i8>(), stringify::<i8>),
+        (TypeId::of::<i16>(), stringify::<i16>),
+        (TypeId::of::<i32>(), stringify::<i32>),
+        (TypeId::of::<i64>(), stringify::<i64>),
+        (TypeId::of::<u8>(), stringify::<u8>),
+        (TypeId::of::<u16>(), stringify::<u16>),
+        (TypeId::of::<u32>(), stringify::<u32>),
+        (TypeId::of::<u64>(), stringify::<u64>),
+        (TypeId::of::<f32>(), stringify::<f32>),
+        (TypeId::of::<f64>(), stringify::<f64>),
+        (TypeId::of::<PathBuf>(), |matches, id| {
+            let occurrences = matches.get_occurrences::<PathBuf>(id)?;
+            Some(
+                occurrences
+                    .map(|values| values.map(|value| value.clone().into()).collect())
+                    .collect(),
+            )
+        }),
+        (TypeId::of::<OsString>(), |matches, id| {
+            let occurrences = matches.get_occurrences::<OsString>(id)?;
+            Some(
+                occurrences
+                    .map(|values| values.cloned().collect())
+                    .collect(),
+            )
+        }),
+        (TypeId::of::<Ipv4Addr>(), stringify::<Ipv4Addr>),
+        (TypeId::of::<Ipv6Addr>(), stringify::<Ipv6Addr>),
+        (TypeId::of::<IpAddr>(), stringify::<IpAddr>),
+    ];
+    match stringifiers.iter().find(|(ty, _)| type_id == *ty) {
+        Some((_, stringifier)) => stringifier(matches, id),
+        None => stringify::<String>(matches, id),
+    }
+}
+
+/// The values of each occurrence of an arg.
+type Occurrences = Vec<Vec<OsString>>;
+
+type Stringifier = fn(&clap::ArgMatches, &str) -> Option<Occurrences>;
+
+fn stringify<T: ToString + Clone + Send + Sync + 'static>(
+    matches: &clap::ArgMatches,
+    id: &str,
+) -> Option<Occurrences> {
+    let occurrences = matches.get_occurrences::<T>(id)?;
+    Some(
+        occurrences
+            .map(|values| values.map(|value| value.to_string().into()).collect())
+            .collect(),
+    )
+}
+
+/// The values of each occurrence of a composite arg and then all the values of each part.
+fn composite_values(
+    matches: &clap::ArgMatches,
+    id: &str,
+    parts: &[&str],
+) -> Option<(Occurrences, Vec<Vec<OsString>>)> {
+    let occurrences = matches
+        .get_occurrences::<CompositeValue>(id)?
+        .map(Iterator::collect::<Vec<_>>)
+        .collect::<Vec<_>>();
+    let values = occurrences.iter().flatten().collect::<Vec<_>>();
+    let parts = (0..parts.len())
+        .map(|i| {
+            values
+                .iter()
+                .map(|value| value.parts[i].1.clone().into())
+                .collect()
+        })
+        .collect();
+    let occurrences = occurrences
+        .iter()
+        .map(|values| {
+            values
+                .iter()
+                .map(|value| value.value.clone().into())
+                .collect()
+        })
+        .collect();
+    Some((occurrences, parts))
+}
+
+/// Returns true if the values of an arg are grouped by occurrence, as the arg may occur many
+/// times with many values each time.  A positional only occurs once.
+fn is_grouped(arg: &clap::Arg) -> bool {
+    matches!(arg.get_action(), ArgAction::Append)
+        && !arg.is_positional()
+        && arg.get_num_args().is_some_and(|r| r.max_values() > 1)
+}
+
+/// A variable for the values of an arg, which is many-valued if the arg is.
+fn values_var(name: String, values: Vec<OsString>, is_many: bool) -> Option<Var> {
+    if is_many {
+        Some(Var::Many(name, values))
+    } else {
+        values
+            .into_iter()
+            .next()
+            .map(|value| Var::Single(name, value))
+    }
+}
+
+/// Match the id(s) of the arg(s) which were selected from a group.
+fn match_group(group: &clap::ArgGroup, matches: &clap::ArgMatches, path: &[&str]) -> Option<Match> {
+    let ids = matches
+        .get_many::<clap::Id>(group.get_id().as_str())?
+        .map(|id| OsString::from(id.as_str()))
+        .collect();
+    Some(Match {
+        path: path.iter().map(ToString::to_string).collect(),
+        id: group.get_id().to_string(),
+        multiple: group.clone().is_multiple(),
+        occurrences: vec![ids],
+        grouped: false,
+        parts: vec![],
+        source: None,
+        indices: None,
+    })
+}
+
+/// Collect the variables into a single associative array, named after the prefix.
+///
+/// Many-valued variables are joined with a space.
+fn assoc_array(vars: &[Var]) -> Var {
+    let entries: IndexMap<String, OsString> = vars
+        .iter()
+        .flat_map(Var::ungroup)
+        .filter_map(|var| match var {
+            Var::Single(name, value) => Some((name, value)),
+            Var::Many(name, values) => Some((name, values.join(OsStr::new(" ")))),
+            Var::Map(..) | Var::Grouped(..) => None,
+        })
+        .collect();
+    Var::Map(String::new(), entries)
+}
+
+/// The variable names of the args which were explicitly present on the command line,
+/// rather than taken from an environment variable or default.
+fn present_ids(
+    cmd: &clap::Command,
+    matches: &clap::ArgMatches,
+    naming: VarNaming,
+    path: &[&str],
+) -> Vec<OsString> {
+    let mut ids: Vec<OsString> = cmd
+        .get_arguments()
+        .filter(|arg| is_cli(matches, arg.get_id().as_str()))
+        .map(|arg| var_name(naming, path, arg.get_id().as_str()).into())
+        .collect();
+    if let Some((sub_name, sub_matches)) = matches.subcommand() {
+        if let Some(sub_cmd) = cmd.find_subcommand(sub_name) {
+            let sub_path = [path, &[sub_name]].concat();
+            ids.extend(present_ids(sub_cmd, sub_matches, naming, &sub_path));
+        }
+    }
+    ids
+}
+
+/// The names of the selected subcommands, outermost first.
+fn subcommand_path(matches: &clap::ArgMatches) -> Vec<String> {
+    std::iter::successors(matches.subcommand(), |(_, sub_matches)| {
+        sub_matches.subcommand()
+    })
+    .map(|(name, _)| name.to_string())
+    .collect()
+}
+
+/// The name of the variable which holds the selected subcommand.
+const SUBCOMMAND: &str = "subcommand";
+
+/// Returns true if a subcommand may be selected, including an external subcommand.
+fn has_subcommands(cmd: &clap::Command) -> bool {
+    cmd.has_subcommands() || cmd.is_allow_external_subcommands_set()
+}
+
+/// The name of the variable which holds the args of an external subcommand.
+const EXTERNAL_ARGS: &str = "subcommand_args";
+
+/// The name of the variable which holds the ids of the args present on the command line.
+const PRESENT: &str = "present";
+
+/// The suffix of the variable which holds the source of the value of an arg.
+const SOURCE: &str = "source";
+
+/// The suffix of the variable which holds the number of occurrences of a grouped arg.
+///
+/// This differs from the `_count` suffix of the many-valued variables of POSIX shells, which
+/// holds the number of values.
+const OCCURRENCES: &str = "occurrences";
+
+/// The suffix of the variable which holds the indices of the values of an arg, which restart at
+/// 1 within each subcommand.
+const INDICES: &str = "indices";
+
+/// The name of the variable which holds the order in which args occurred.
+const ORDER: &str = "order";
+
+/// Non-standard settings which control the output, rather than the parsing.
+#[derive(Clone, Debug)]
+pub(crate) struct Settings {
+    pub assoc_array: bool,
+    pub var_prefix: String,
+    pub var_naming: VarNaming,
+    pub value_source: bool,
+    pub declare_all: bool,
+    pub indices: bool,
+    pub format: OutputFormat,
+}
+
+/// Build a variable name from a subcommand path and an id.
+///
+/// For example, the id `name` in the subcommand path `env create` becomes `env_create_name`.
+/// Each part is converted to a legal shell identifier using the `naming` convention.
+fn var_name(naming: VarNaming, path: &[&str], id: &str) -> String {
+    path.iter()
+        .chain(std::iter::once(&id))
+        .map(|part| naming.apply(part))
+        .join("_")
+}
+
+/// The variable prefix, converted using the naming convention.
+///
+/// Unlike ids, the prefix is not sanitized and must already be a legal shell identifier.
+fn var_prefix(settings: &Settings) -> Result<String, String> {
+    let prefix = &settings.var_prefix;
+    let is_legal = prefix.chars().all(output::is_ident_char)
+        && prefix.chars().next().is_some_and(|c| !c.is_ascii_digit());
+    if is_legal {
+        Ok(settings.var_naming.apply(prefix))
+    } else {
+        Err(format!(
+            "invalid variable prefix `{prefix}`, it must be a legal shell identifier"
+        ))
+    }
+}
+
+/// Check that no two variables share a name once ids have been converted using the naming
+/// convention, for example the ids `dry-run` and `dry_run`.
+fn check_var_names(
+    cmd: &clap::Command,
+    settings: &Settings,
+    composite_parts: &CompositeParts,
+    prefix: &str,
+) -> Result<(), String> {
+    let mut names = IndexMap::new();
+    let mut stems = vec![];
+    if settings.assoc_array {
+        let present = var_name(settings.var_naming, &[], PRESENT);
+        names.insert(present.clone(), String::from("the present ids"));
+        // The associative array is named after the prefix itself and, like the present ids,
+        // is output as an array with derived variables in POSIX shells.
+        if settings.format == OutputFormat::Posix {
+            stems.push((present, Derived::Values, String::from("the present ids")));
+            stems.push((
+                String::new(),
+                Derived::Values,
+                String::from("the associative array"),
+            ));
+        }
+    }
+    collect_var_names(cmd, settings, composite_parts, &[], &mut names, &mut stems)
+        .and_then(|()| check_derived_names(&names, &stems, settings.format))
+        .map_err(|(name, a, b)| {
+            format!(
+                "{a} and {b} both map to the variable `{}`",
+                full_name(prefix, &name)
+            )
+        })
+}
+
+/// The names derived from the name of a many-valued variable.
+#[derive(Clone, Copy)]
+enum Derived {
+    /// The count and values of a many-valued variable in POSIX shells, such as `files_count`
+    /// and `files_0`.
+    Values,
+    /// The values of each occurrence of a grouped arg, such as `pair_0`, along with their
+    /// count and values in POSIX shells, such as `pair_0_count` and `pair_0_1`.
+    Occurrences,
+}
+
+impl Derived {
+    /// Returns true if `name` is derived from the variable named `stem` in the output format.
+    fn derives(self, stem: &str, name: &str, format: OutputFormat) -> bool {
+        let suffix = if stem.is_empty() {
+            Some(name)
+        } else {
+            name.strip_prefix(stem)
+                .and_then(|suffix| suffix.strip_prefix('_'))
+        };
+        let Some(suffix) = suffix else {
+            return false;
+        };
+        let is_index = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+        match self {
+            Derived::Values => suffix == "count" || is_index(suffix),
+            Derived::Occurrences => match suffix.split_once('_') {
+                Some((i, rest)) => {
+                    format == OutputFormat::Posix
+                        && is_index(i)
+                        && (rest == "count" || is_index(rest))
+                }
+                None => is_index(suffix),
+            },
+        }
+    }
+}
+
+/// Check that no variable is named like one derived from a many-valued variable, such as an
+/// arg `files_count` alongside a many-valued arg `files`.
+fn check_derived_names(
+    names: &IndexMap<String, String>,
+    stems: &[(String, Derived, String)],
+    format: OutputFormat,
+) -> Result<(), (String, String, String)> {
+    for (stem, derived, stem_source) in stems {
+        let derives = |name: &&String| derived.derives(stem, name, format);
+        if let Some((name, source)) = names.iter().find(|(name, _)| derives(name)) {
+            return Err((name.clone(), stem_source.clone(), source.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// Collect the name of every variable which may be emitted for `cmd`, mapped to a description
+/// of its source, returning the name and both sources on the first collision.
+fn collect_var_names(
+    cmd: &clap::Command,
+    settings: &Settings,
+    composite_parts: &CompositeParts,
+    path: &[&str],
+    names: &mut IndexMap<String, String>,
+    stems: &mut Vec<(String, Derived, String)>,
+) -> Result<(), (String, String, String)> {
+    let describe = |kind: &str, id: &str| {
+        if path.is_empty() {
+            format!("{kind} `{id}`")
+        } else {
+            format!("{kind} `{id}` of subcommand `{}`", path.join(" "))
+        }
+    };
+    let args = cmd
+        .get_arguments()
+        .filter(|arg| {
+            matches!(
+                arg.get_action(),
+                ArgAction::Set
+                    | ArgAction::Append
+                    | ArgAction::SetTrue
+                    | ArgAction::SetFalse
+                    | ArgAction::Count
+            )
+        })
+        .collect::<Vec<_>>();
+    let arg_names = args.iter().map(|arg| {
+        let id = arg.get_id().as_str();
+        (id.to_string(), describe("arg", id))
+    });
+    let part_names = args.iter().flat_map(|arg| {
+        let id = arg.get_id().as_str();
+        let parts = composite_parts.get(path, arg).unwrap_or_default();
+        parts.iter().map(move |part| {
+            (
+                format!("{id}_{part}"),
+                describe(&format!("the `{part}` part of arg"), id),
+            )
+        })
+    });
+    let count_names = args.iter().filter(|arg| is_grouped(arg)).map(|arg| {
+        let id = arg.get_id().as_str();
+        (
+            format!("{id}_{OCCURRENCES}"),
+            describe("the occurrence count of arg", id),
+        )
+    });
+    let source_names = args.iter().filter(|_| settings.value_source).map(|arg| {
+        let id = arg.get_id().as_str();
+        (format!("{id}_{SOURCE}"), describe("the source of arg", id))
+    });
+    let index_names = args
+        .iter()
+        .filter(|_| settings.indices)
+        .map(|arg| {
+            let id = arg.get_id().as_str();
+            (
+                format!("{id}_{INDICES}"),
+                describe("the indices of arg", id),
+            )
+        })
+        .collect::<Vec<_>>();
+    let order = settings
+        .indices
+        .then(|| (ORDER.to_string(), describe("the", ORDER)));
+    let groups = cmd.get_groups().map(|group| {
+        let id = group.get_id().as_str();
+        (id.to_string(), describe("group", id))
+    });
+    let subcommand = has_subcommands(cmd)
+        .then(|| (SUBCOMMAND.to_string(), describe("the selected", SUBCOMMAND)));
+    let external_args = cmd.is_allow_external_subcommands_set().then(|| {
+        (
+            EXTERNAL_ARGS.to_string(),
+            describe("the args of an external", SUBCOMMAND),
+        )
+    });
+    for (id, source) in arg_names
+        .chain(part_names)
+        .chain(count_names)
+        .chain(source_names)
+        .chain(index_names.clone())
+        .chain(order.clone())
+        .chain(groups)
+        .chain(subcommand)
+        .chain(external_args.clone())
+    {
+        let name = var_name(settings.var_naming, path, &id);
+        if let Some(existing) = names.get(&name) {
+            return Err((name, existing.clone(), source));
+        }
+        names.insert(name, source);
+    }
+    let many_args = args.iter().filter(|arg| {
+        matches!(arg.get_action(), ArgAction::Append)
+            || (matches!(arg.get_action(), ArgAction::Set) && arg.is_many())
+    });
+    let many_names = many_args.flat_map(|arg| {
+        let id = arg.get_id().as_str();
+        let parts = composite_parts.get(path, arg).unwrap_or_default();
+        std::iter::once((id.to_string(), describe("the values of arg", id))).chain(
+            parts.iter().map(move |part| {
+                (
+                    format!("{id}_{part}"),
+                    describe(&format!("the values of the `{part}` part of arg"), id),
+                )
+            }),
+        )
+    });
+    let many_groups = cmd
+        .get_groups()
+        .filter(|group| (*group).clone().is_multiple())
+        .map(|group| {
+            let id = group.get_id().as_str();
+            (id.to_string(), describe("the ids of group", id))
+        });
+    let values = many_names
+        .chain(many_groups)
+        .chain(index_names)
+        .chain(order)
+        .chain(external_args)
+        .filter(|_| settings.format == OutputFormat::Posix)
+        .map(|(id, source)| (id, Derived::Values, source));
+    let occurrences = args.iter().filter(|arg| is_grouped(arg)).map(|arg| {
+        let id = arg.get_id().as_str();
+        (
+            id.to_string(),
+            Derived::Occurrences,
+            describe("the occurrences of arg", id),
+        )
+    });
+    stems.extend(
+        values.chain(occurrences).map(|(id, derived, source)| {
+            (var_name(settings.var_naming, path, &id), derived, source)
+        }),
+    );
+    for sub_cmd in cmd.get_subcommands() {
+        let sub_path = [path, &[sub_cmd.get_name()]].concat();
+        collect_var_names(sub_cmd, settings, composite_parts, &sub_path, names, stems)?;
+    }
+    Ok(())
+}
+
+/// An error in the spec, styled in the same way as a clap error.
+fn error_output(message: &impl Display) -> Output {
+    let error = *clap::builder::Styles::styled().get_error();
+    let mut styled = StyledStr::new();
+    let _ = writeln!(styled, "{error}error:{error:#} {message}");
+    Output::Cat(CatCmd::new(styled, ExitCode::Error))
+}
+
 /// Extension trait for `clap::Arg` to determine if it is many-valued.
 trait IsManyEx {
     /// Returns true if the argument is many-valued.
@@ -74,9 +862,7 @@ trait IsManyEx {
 
 impl IsManyEx for clap::Arg {
     fn is_many(&self) -> bool {
-        self.get_num_args()
-            .map(|r| r.max_values() > 1)
-            .or(self.get_value_delimiter().map(|_| true))
-            .unwrap_or(false)
+        self.get_num_args().is_some_and(|r| r.max_values() > 1)
+            || self.get_value_delimiter().is_some()
     }
 }
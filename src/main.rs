@@ -3,10 +3,10 @@ use anstream::ColorChoice;
 use clap::Parser;
 use clap::builder::StyledStr;
 use clap_complete::Shell;
-use claptrap::command::Command;
-use claptrap::output::{CatCmd, ExitCode, Output};
+use claptrap::command::{Command, Diagnostic, SpecSource, render_check, render_diagnostics};
+use claptrap::output::{CatCmd, ExitCode, Output, OutputFormat};
 use claptrap::parse;
-use std::ffi::OsString;
+use error::Error;
 use std::io::Write;
 use std::panic;
 use std::panic::AssertUnwindSafe;
@@ -23,6 +23,10 @@ fn main() -> anyhow::Result<()> {
             run_generate_completions(&cli.spec, shell, output)?;
             exit(0);
         }
+        Some(SubCommand::Check) => {
+            let is_ok = run_check(&cli.spec, cli.format, cli.show_panic)?;
+            exit(if is_ok { 0 } else { 1 });
+        }
         Some(SubCommand::Man { output }) => {
             run_generate_man(&cli.spec, output)?;
             exit(0);
@@ -40,22 +44,22 @@ fn main() -> anyhow::Result<()> {
             }
             let mut stdout =
                 anstream::AutoStream::new(std::io::stdout().lock(), ColorChoice::Always);
-            match panic::catch_unwind(AssertUnwindSafe(|| run_app(&cli.spec, cli.args))) {
+            match panic::catch_unwind(AssertUnwindSafe(|| run_app(&cli))) {
                 Ok(val) => match val {
                     Ok(output) => {
-                        write!(stdout, "{output}")?;
+                        write!(stdout, "{}", output.render(cli.format))?;
                         stdout.flush()?;
                         exit(0);
                     }
                     Err(err) => {
-                        write!(stdout, "{err}")?;
+                        write!(stdout, "{}", err.render(cli.format))?;
                         stdout.flush()?;
                         exit(0);
                     }
                 },
                 Err(err) => {
                     let panic = panic_output(err);
-                    write!(stdout, "{panic}")?;
+                    write!(stdout, "{}", panic.render(cli.format))?;
                     stdout.flush()?;
                     exit(0);
                 }
@@ -71,7 +75,7 @@ fn run_generate_completions(
 ) -> anyhow::Result<()> {
     let spec = read_spec(spec_path)?;
     let cmd = toml::from_str::<Command>(&spec)?;
-    let mut clap_cmd = clap::Command::from(cmd).no_binary_name(true);
+    let mut clap_cmd = clap::Command::try_from(cmd)?.no_binary_name(true);
     let name = clap_cmd.get_name().to_string();
     let mut buffer: Vec<u8> = vec![];
     clap_complete::generate(shell, &mut clap_cmd, name, &mut buffer);
@@ -83,10 +87,31 @@ fn run_generate_completions(
     Ok(())
 }
 
+fn run_check(spec_path: &Path, format: OutputFormat, show_panic: bool) -> anyhow::Result<bool> {
+    let spec = read_spec(spec_path)?;
+    let diagnostics = match toml::from_str::<Command>(&spec) {
+        Ok(cmd) => {
+            // The clap debug asserts report problems by panicking.
+            if !show_panic {
+                panic::set_hook(Box::new(|_| {}));
+            }
+            cmd.check()
+        }
+        Err(err) => vec![Diagnostic::from(err)],
+    };
+    let path = spec_path_name(spec_path);
+    let source = SpecSource {
+        path: &path,
+        text: &spec,
+    };
+    print!("{}", render_check(&diagnostics, source, format));
+    Ok(diagnostics.is_empty())
+}
+
 fn run_generate_man(spec_path: &Path, output: Option<PathBuf>) -> anyhow::Result<()> {
     let spec = read_spec(spec_path)?;
     let cmd = toml::from_str::<Command>(&spec)?;
-    let clap_cmd = clap::Command::from(cmd).no_binary_name(true);
+    let clap_cmd = clap::Command::try_from(cmd)?.no_binary_name(true);
     let mut buffer: Vec<u8> = vec![];
     clap_mangen::Man::new(clap_cmd).render(&mut buffer)?;
     if let Some(output_path) = output {
@@ -97,20 +122,54 @@ fn run_generate_man(spec_path: &Path, output: Option<PathBuf>) -> anyhow::Result
     Ok(())
 }
 
-fn run_app(spec_path: &Path, args: Vec<OsString>) -> error::Result<Output> {
-    let spec = read_spec(spec_path)?;
-    let cmd = toml::from_str::<Command>(&spec)?;
-    Ok(parse(cmd, args))
+fn run_app(cli: &cli::Cli) -> error::Result<Output> {
+    let spec = read_spec(&cli.spec)?;
+    let path = spec_path_name(&cli.spec);
+    let source = SpecSource {
+        path: &path,
+        text: &spec,
+    };
+    let mut cmd = Command::from_toml(&spec)
+        .map_err(|diagnostics| Error::spec(render_diagnostics(&diagnostics, Some(source))))?;
+    if cli.assoc_array {
+        cmd = cmd.assoc_array(true);
+    }
+    if let Some(prefix) = &cli.var_prefix {
+        cmd = cmd.var_prefix(prefix);
+    }
+    if let Some(naming) = cli.var_naming {
+        cmd = cmd.var_naming(naming);
+    }
+    if cli.value_source {
+        cmd = cmd.value_source(true);
+    }
+    if cli.declare_all {
+        cmd = cmd.declare_all(true);
+    }
+    if cli.indices {
+        cmd = cmd.indices(true);
+    }
+    cmd = cmd.format(cli.format);
+    Ok(parse(cmd, cli.args.clone()))
 }
 
 fn read_spec(spec: &Path) -> std::io::Result<String> {
-    Ok(if spec == PathBuf::from("-") {
+    Ok(if spec == Path::new("-") {
         std::io::read_to_string(std::io::stdin())?
     } else {
         std::fs::read_to_string(spec)?
     })
 }
 
+/// The path of the spec as shown in diagnostics.
+fn spec_path_name(spec: &Path) -> String {
+    if spec == Path::new("-") {
+        String::from("<stdin>")
+    } else {
+        spec.display().to_string()
+    }
+}
+
 fn panic_output(err: Box<dyn std::any::Any + Send>) -> Output {
     let panic_message = if let Some(message) = err.downcast_ref::<String>() {
         message
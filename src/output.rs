@@ -1,40 +1,354 @@
+use crate::output::bash::Bash;
+use crate::output::fish::Fish;
+use crate::output::posix::Posix;
+use crate::output::zsh::Zsh;
 use clap::builder::StyledStr;
+use indexmap::IndexMap;
 use itertools::Itertools;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
 use std::fmt::Display;
 
-// The prefix for variables output by claptrap
-const PREFIX: &str = "claptrap";
+mod bash;
+mod fish;
+mod posix;
+mod zsh;
+
+// The default prefix for variables output by claptrap
+pub(crate) const DEFAULT_PREFIX: &str = "claptrap";
+
+/// The format in which output is rendered.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Output for bash
+    #[default]
+    Bash,
+    /// Output for zsh
+    Zsh,
+    /// Output for fish
+    Fish,
+    /// Output for POSIX sh, without arrays
+    Posix,
+    /// Output as JSON, for consumers other than a shell
+    Json,
+}
+
+/// The naming convention used to build variable names from ids.
+///
+/// All conventions replace any character which is not legal in a shell identifier with `_`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum VarNaming {
+    /// Use ids as-is, for example `dryRun` becomes `dryRun`
+    #[default]
+    Verbatim,
+    /// Convert ids to snake_case, for example `dryRun` becomes `dry_run`
+    SnakeCase,
+    /// Convert ids to SCREAMING_SNAKE_CASE, for example `dryRun` becomes `DRY_RUN`
+    ScreamingSnake,
+}
+
+impl VarNaming {
+    /// Convert an id into a legal shell identifier using this convention.
+    pub fn apply(self, id: &str) -> String {
+        match self {
+            VarNaming::Verbatim => id
+                .chars()
+                .map(|c| if is_ident_char(c) { c } else { '_' })
+                .collect(),
+            VarNaming::SnakeCase => snake_case(id),
+            VarNaming::ScreamingSnake => snake_case(id).to_ascii_uppercase(),
+        }
+    }
+}
+
+/// Returns true if `c` may appear in a shell identifier.
+pub(crate) fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Convert an id to snake_case, splitting words at a lowercase to uppercase boundary.
+fn snake_case(id: &str) -> String {
+    let mut name = String::with_capacity(id.len());
+    let mut prev: Option<char> = None;
+    for c in id.chars() {
+        if c.is_ascii_uppercase()
+            && prev.is_some_and(|p| p.is_ascii_lowercase() || p.is_ascii_digit())
+        {
+            name.push('_');
+        }
+        name.push(if is_ident_char(c) {
+            c.to_ascii_lowercase()
+        } else {
+            '_'
+        });
+        prev = Some(c);
+    }
+    name
+}
 
 /// Represents the output of a claptrap command.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
 pub enum Output {
     Cat(CatCmd),
-    Variables(Vec<Var>),
+    Variables(Variables),
+}
+
+impl Output {
+    /// Render the output in the given format.
+    pub fn render(&self, format: OutputFormat) -> Rendered<'_> {
+        Rendered {
+            output: self,
+            format,
+        }
+    }
 }
 
 impl Display for Output {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Output::Cat(cmd) => write!(f, "{cmd}"),
-            Output::Variables(vars) => write!(f, "{}", vars.iter().format("\n")),
+        write!(f, "{}", self.render(OutputFormat::Bash))
+    }
+}
+
+/// An `Output` rendered in a given `OutputFormat`.
+#[derive(Debug)]
+pub struct Rendered<'a> {
+    output: &'a Output,
+    format: OutputFormat,
+}
+
+impl Display for Rendered<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.format {
+            OutputFormat::Bash => fmt_output::<Bash>(self.output, f),
+            OutputFormat::Zsh => fmt_output::<Zsh>(self.output, f),
+            OutputFormat::Fish => fmt_output::<Fish>(self.output, f),
+            OutputFormat::Posix => fmt_output::<Posix>(self.output, f),
+            OutputFormat::Json => {
+                let json = serde_json::to_string(self.output).map_err(|_| std::fmt::Error)?;
+                write!(f, "{json}")
+            }
+        }
+    }
+}
+
+/// The variables extracted from the matched arguments.
+///
+/// When serialized, the matched args and groups are output rather than the shell variables,
+/// such that the output does not depend on the variable naming.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct Variables {
+    /// The prefix of all variable names.
+    #[serde(skip)]
+    pub prefix: String,
+    /// The names of the selected subcommands, outermost first.
+    pub subcommand: Vec<String>,
+    #[serde(flatten)]
+    pub matches: Matches,
+    #[serde(skip)]
+    pub variables: Vec<Var>,
+}
+
+impl Variables {
+    pub fn new(
+        prefix: String,
+        subcommand: Vec<String>,
+        matches: Matches,
+        variables: Vec<Var>,
+    ) -> Self {
+        Self {
+            prefix,
+            subcommand,
+            matches,
+            variables,
+        }
+    }
+}
+
+/// The args and groups matched in a command and its selected subcommands.
+#[derive(Debug, Default, Eq, PartialEq, Serialize)]
+pub struct Matches {
+    pub args: Vec<Match>,
+    pub groups: Vec<Match>,
+    /// The args of an external subcommand, if one was selected.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_external_args"
+    )]
+    pub external_args: Option<Vec<OsString>>,
+}
+
+fn serialize_external_args<S: Serializer>(
+    args: &Option<Vec<OsString>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(args.iter().flatten().map(|arg| JsonValue(arg)))
+}
+
+/// An arg or group matched in a command.
+///
+/// Unlike a `Var`, a match is identified by its id and the path of the subcommand which
+/// declares it, rather than by a variable name.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Match {
+    /// The names of the subcommands which lead to the command declaring the arg, outermost
+    /// first.
+    pub path: Vec<String>,
+    pub id: String,
+    pub multiple: bool,
+    /// The values of each occurrence of the arg.
+    pub occurrences: Vec<Vec<OsString>>,
+    /// Whether the values are also output grouped by occurrence.
+    pub grouped: bool,
+    /// The values of each part of a composite value, such as the `port` of a `url`.
+    pub parts: Vec<(&'static str, Vec<OsString>)>,
+    /// The source of the value, if `value-source` is set.
+    pub source: Option<&'static str>,
+    /// The indices of the values on the command line, if `indices` is set, relative to the
+    /// args of the subcommand.
+    pub indices: Option<Vec<usize>>,
+}
+
+impl Match {
+    /// The values of all occurrences.
+    pub fn values(&self) -> Vec<OsString> {
+        self.occurrences.iter().flatten().cloned().collect()
+    }
+}
+
+impl Serialize for Match {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Match", 8)?;
+        let values = self.values();
+        state.serialize_field("path", &self.path)?;
+        state.serialize_field("id", &self.id)?;
+        state.serialize_field("multiple", &self.multiple)?;
+        state.serialize_field("value", &JsonValues(&values, self.multiple))?;
+        if self.grouped {
+            let occurrences = self
+                .occurrences
+                .iter()
+                .map(|values| JsonValues(values, true))
+                .collect::<Vec<_>>();
+            state.serialize_field("occurrences", &occurrences)?;
+        }
+        if !self.parts.is_empty() {
+            let parts = self
+                .parts
+                .iter()
+                .map(|(part, values)| (part, JsonValues(values, self.multiple)))
+                .collect::<IndexMap<_, _>>();
+            state.serialize_field("parts", &parts)?;
         }
+        if let Some(source) = self.source {
+            state.serialize_field("source", source)?;
+        }
+        if let Some(indices) = &self.indices {
+            state.serialize_field("indices", indices)?;
+        }
+        state.end()
     }
 }
 
 /// Represents a variable output by claptrap.
-#[derive(Debug, Eq, PartialEq)]
+///
+/// A `Var::Map` with an empty name is named after the prefix itself.  A `Var::Grouped` holds
+/// the values of an arg grouped by occurrence, along with the name of the variable which
+/// holds the number of occurrences.
+///
+/// Values are `OsString`s as the values of args which use the `os-string` or `path` value
+/// parsers need not be valid UTF-8.
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Var {
-    Single(String, String),
-    Many(String, Vec<String>),
+    Single(String, OsString),
+    Many(String, Vec<OsString>),
+    Map(String, IndexMap<String, OsString>),
+    Grouped(String, String, Vec<Vec<OsString>>),
+}
+
+impl Var {
+    /// The name of the variable, without the prefix.
+    pub fn name(&self) -> &str {
+        match self {
+            Var::Single(name, _)
+            | Var::Many(name, _)
+            | Var::Map(name, _)
+            | Var::Grouped(name, _, _) => name,
+        }
+    }
+
+    /// A `Var::Grouped` as a `Var::Many` of all values, followed by the number of occurrences
+    /// and a `Var::Many` of the values of each occurrence, for example `pair=(a b c d)`,
+    /// `pair_occurrences=2`, `pair_0=(a b)` and `pair_1=(c d)`.  Any other variable is returned as-is.
+    pub(crate) fn ungroup(&self) -> Vec<Var> {
+        let Var::Grouped(name, count, occurrences) = self else {
+            return vec![self.clone()];
+        };
+        let values = occurrences.iter().flatten().cloned().collect();
+        [
+            Var::Many(name.to_owned(), values),
+            Var::Single(count.to_owned(), occurrences.len().to_string().into()),
+        ]
+        .into_iter()
+        .chain(
+            occurrences
+                .iter()
+                .enumerate()
+                .map(|(i, values)| Var::Many(format!("{name}_{i}"), values.clone())),
+        )
+        .collect()
+    }
+
+    /// The entries of a `Var::Map` as a `Var::Many` of `key=value` strings, for dialects
+    /// which do not support associative arrays.
+    fn map_as_many(name: &str, entries: &IndexMap<String, OsString>) -> Self {
+        Var::Many(
+            name.to_string(),
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let mut entry = OsString::from(format!("{key}="));
+                    entry.push(value);
+                    entry
+                })
+                .collect(),
+        )
+    }
 }
 
 impl Display for Var {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Var::Single(name, value) => write!(f, "{}_{}={}", PREFIX, name, value),
-            Var::Many(name, values) => {
-                write!(f, "{}_{}=({})", PREFIX, name, values.iter().join(" "))
-            }
+        write!(f, "{}", Bash::var(DEFAULT_PREFIX, self))
+    }
+}
+
+/// A value serialized as a string if it is valid UTF-8, and otherwise as an array of its bytes
+/// as JSON cannot represent arbitrary bytes in a string.
+struct JsonValue<'a>(&'a OsStr);
+
+impl Serialize for JsonValue<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.0.to_str() {
+            Some(value) => serializer.serialize_str(value),
+            None => serializer.collect_seq(self.0.as_encoded_bytes()),
+        }
+    }
+}
+
+/// Values serialized as an array if many-valued, and otherwise as the first value or `null`.
+struct JsonValues<'a>(&'a [OsString], bool);
+
+impl Serialize for JsonValues<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let JsonValues(values, multiple) = *self;
+        let mut values = values.iter().map(|value| JsonValue(value));
+        if multiple {
+            serializer.collect_seq(values)
+        } else {
+            values.next().serialize(serializer)
         }
     }
 }
@@ -54,13 +368,26 @@ impl Display for ExitCode {
     }
 }
 
+impl Serialize for ExitCode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i32(*self as i32)
+    }
+}
+
 /// A `cat` command invocation.
-#[derive(Debug, Eq, PartialEq)]
+///
+/// When serialized the data is rendered as plain text, without any styling.
+#[derive(Debug, Eq, PartialEq, Serialize)]
 pub struct CatCmd {
+    #[serde(rename = "text", serialize_with = "serialize_plain")]
     pub data: StyledStr,
     pub exit_code: ExitCode,
 }
 
+fn serialize_plain<S: Serializer>(data: &StyledStr, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(data)
+}
+
 impl CatCmd {
     pub fn new(cmd: StyledStr, exit_code: ExitCode) -> Self {
         Self {
@@ -72,11 +399,108 @@ impl CatCmd {
 
 impl Display for CatCmd {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "command cat <<'EOF'\n{}EOF\nexit {}",
-            self.data.ansi(),
-            self.exit_code
-        )
+        write!(f, "{}", Bash::cat(self))
+    }
+}
+
+/// A shell dialect in which output can be rendered.
+///
+/// All rendered output must be safe to `eval` in the shell.
+trait Dialect {
+    /// Render a variable assignment, with the name prefixed by `prefix`.
+    fn var(prefix: &str, var: &Var) -> String;
+
+    /// Render a command which writes the data to stdout and then exits.
+    fn cat(cmd: &CatCmd) -> String;
+}
+
+fn fmt_output<D: Dialect>(output: &Output, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match output {
+        Output::Cat(cmd) => write!(f, "{}", D::cat(cmd)),
+        Output::Variables(vars) => {
+            let rendered = vars.variables.iter().map(|var| D::var(&vars.prefix, var));
+            write!(f, "{}", rendered.format("\n"))
+        }
+    }
+}
+
+/// The full name of a variable, including the prefix.
+pub(crate) fn full_name(prefix: &str, name: &str) -> String {
+    if name.is_empty() {
+        prefix.to_string()
+    } else {
+        format!("{prefix}_{name}")
+    }
+}
+
+/// Wrap a value in single quotes.
+///
+/// A single quote cannot appear inside a single-quoted string and so is written as `\'`
+/// between the quoted parts.  Empty parts are omitted such that the output never contains
+/// `''` inside a word, which zsh treats as an escaped quote when `RC_QUOTES` is set.
+fn single_quote(value: &str) -> String {
+    if value.is_empty() {
+        return String::from("''");
+    }
+    value
+        .split('\'')
+        .map(|part| {
+            if part.is_empty() {
+                String::new()
+            } else {
+                format!("'{part}'")
+            }
+        })
+        .join(r"\'")
+}
+
+/// Quote a value which may not be valid UTF-8, using `quote` if it is valid and otherwise
+/// `escape` on its bytes.
+fn quote_os<'a>(
+    value: &'a OsStr,
+    quote: impl Fn(&'a str) -> Cow<'a, str>,
+    escape: impl Fn(&[u8]) -> String,
+) -> Cow<'a, str> {
+    match value.to_str() {
+        Some(value) => quote(value),
+        None => Cow::Owned(escape(value.as_encoded_bytes())),
+    }
+}
+
+/// Wrap bytes in an ANSI-C quoted string, such as `$'caf\xe9'`.
+///
+/// Printable ASCII characters other than `\` and `'` are kept as-is, all other bytes are
+/// written as `\xNN` escapes.
+fn ansi_c_quote(bytes: &[u8]) -> String {
+    let escaped: String = bytes
+        .iter()
+        .map(|&byte| match byte {
+            b'\\' | b'\'' => format!("\\{}", byte as char),
+            b' '..=b'~' => (byte as char).to_string(),
+            _ => format!("\\x{byte:02x}"),
+        })
+        .collect();
+    format!("$'{escaped}'")
+}
+
+/// Render a `cat` of the data using a heredoc, followed by an `exit`.
+fn heredoc(cmd: &CatCmd) -> String {
+    let data = cmd.data.ansi().to_string();
+    let delimiter = heredoc_delimiter(&data);
+    format!(
+        "command cat <<'{delimiter}'\n{data}{delimiter}\nexit {}",
+        cmd.exit_code
+    )
+}
+
+/// Choose a heredoc delimiter which does not appear as a line in `data`.
+///
+/// The data may contain user input, such as an unexpected argument echoed back in an
+/// error message, and so a fixed delimiter could be used to terminate the heredoc early.
+fn heredoc_delimiter(data: &str) -> String {
+    let mut delimiter = String::from("EOF");
+    while data.lines().any(|line| line == delimiter) {
+        delimiter.push('_');
     }
+    delimiter
 }
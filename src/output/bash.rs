@@ -0,0 +1,49 @@
+use crate::output::posix::quote;
+use crate::output::{CatCmd, Dialect, Var, ansi_c_quote, full_name, heredoc, quote_os};
+use itertools::Itertools;
+use std::borrow::Cow;
+use std::ffi::OsStr;
+
+/// The bash shell.
+///
+/// Bash shares the quoting rules of the POSIX shell.  Associative arrays are declared with
+/// `declare -g` so that they are global even when evaluated inside a function.
+pub struct Bash;
+
+impl Dialect for Bash {
+    fn var(prefix: &str, var: &Var) -> String {
+        match var {
+            Var::Single(name, value) => {
+                format!("{}={}", full_name(prefix, name), quote_value(value))
+            }
+            Var::Many(name, values) => format!(
+                "{}=({})",
+                full_name(prefix, name),
+                values.iter().map(|value| quote_value(value)).join(" ")
+            ),
+            Var::Map(name, entries) => format!(
+                "declare -gA {}=({})",
+                full_name(prefix, name),
+                entries
+                    .iter()
+                    .map(|(key, value)| format!("[{}]={}", quote(key), quote_value(value)))
+                    .join(" ")
+            ),
+            Var::Grouped(..) => var
+                .ungroup()
+                .iter()
+                .map(|var| Self::var(prefix, var))
+                .join("\n"),
+        }
+    }
+
+    fn cat(cmd: &CatCmd) -> String {
+        heredoc(cmd)
+    }
+}
+
+/// Quote an `OsStr` value with `quote` if it is valid UTF-8, and otherwise with ANSI-C quoting,
+/// such as `$'caf\xe9'`, as bash reads each `\xNN` escape as a raw byte.
+fn quote_value(value: &OsStr) -> Cow<'_, str> {
+    quote_os(value, quote, ansi_c_quote)
+}
@@ -0,0 +1,78 @@
+use crate::output::{CatCmd, Dialect, Var, full_name, quote_os};
+use itertools::Itertools;
+use std::borrow::Cow;
+use std::ffi::OsStr;
+
+/// The fish shell.
+///
+/// Variables are declared with `set -g` as a local variable would otherwise only be visible
+/// inside the `eval` or `source` which defines it.  Fish does not support associative arrays
+/// and so a `Var::Map` is emitted as a list of `key=value` strings.
+pub struct Fish;
+
+impl Dialect for Fish {
+    fn var(prefix: &str, var: &Var) -> String {
+        match var {
+            Var::Single(name, value) => {
+                format!(
+                    "set -g -- {} {}",
+                    full_name(prefix, name),
+                    quote_value(value)
+                )
+            }
+            Var::Many(name, values) if values.is_empty() => {
+                format!("set -g -- {}", full_name(prefix, name))
+            }
+            Var::Many(name, values) => format!(
+                "set -g -- {} {}",
+                full_name(prefix, name),
+                values.iter().map(|value| quote_value(value)).join(" ")
+            ),
+            Var::Map(name, entries) => Self::var(prefix, &Var::map_as_many(name, entries)),
+            Var::Grouped(..) => var
+                .ungroup()
+                .iter()
+                .map(|var| Self::var(prefix, var))
+                .join("\n"),
+        }
+    }
+
+    fn cat(cmd: &CatCmd) -> String {
+        format!(
+            "printf '%s' {}\nexit {}",
+            quote(&cmd.data.ansi().to_string()),
+            cmd.exit_code
+        )
+    }
+}
+
+/// Quote a value such that it is safe to `eval` in fish.
+///
+/// Fish allows `\\` and `\'` escapes inside single quotes, unlike POSIX shells.  A word
+/// starting with `%` is subject to process expansion and so `%` is not considered safe.
+fn quote(value: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-./:@+,=".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(format!(
+            "'{}'",
+            value.replace('\\', r"\\").replace('\'', r"\'")
+        ))
+    }
+}
+
+/// Write each byte of a value which is not valid UTF-8 as a `\xNN` escape outside of any
+/// quotes, which fish reads as a raw byte, and quote the valid parts with `quote`.
+fn quote_value(value: &OsStr) -> Cow<'_, str> {
+    quote_os(value, quote, |bytes| {
+        bytes
+            .utf8_chunks()
+            .flat_map(|chunk| {
+                let valid = (!chunk.valid().is_empty()).then(|| quote(chunk.valid()).into_owned());
+                let invalid = chunk.invalid().iter().map(|byte| format!("\\x{byte:02x}"));
+                valid.into_iter().chain(invalid)
+            })
+            .collect()
+    })
+}
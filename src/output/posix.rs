@@ -0,0 +1,85 @@
+use crate::output::{CatCmd, Dialect, Var, full_name, heredoc, quote_os, single_quote};
+use itertools::Itertools;
+use std::borrow::Cow;
+use std::ffi::OsStr;
+
+/// The POSIX shell, such as `dash` or busybox `sh`.
+///
+/// POSIX shells do not support arrays and so many-valued variables are emitted as a count
+/// followed by one variable per value, for example `claptrap_files_count=2`,
+/// `claptrap_files_0=a` and `claptrap_files_1=b`.  Likewise a `Var::Map` is emitted as a list
+/// of `key=value` strings.  A `Var::Grouped` is emitted as the values of all occurrences, as
+/// for any many-valued variable, followed by the number of occurrences and the values of each
+/// occurrence, for example `claptrap_pair_occurrences=2`, `claptrap_pair_0_count=2` and
+/// `claptrap_pair_0_0=a`.
+pub struct Posix;
+
+impl Dialect for Posix {
+    fn var(prefix: &str, var: &Var) -> String {
+        match var {
+            Var::Single(name, value) => {
+                format!("{}={}", full_name(prefix, name), quote_value(value))
+            }
+            Var::Many(name, values) => {
+                let name = full_name(prefix, name);
+                std::iter::once(format!("{name}_count={}", values.len()))
+                    .chain(
+                        values
+                            .iter()
+                            .enumerate()
+                            .map(|(i, value)| format!("{name}_{i}={}", quote_value(value))),
+                    )
+                    .join("\n")
+            }
+            Var::Map(name, entries) => Self::var(prefix, &Var::map_as_many(name, entries)),
+            Var::Grouped(..) => var
+                .ungroup()
+                .iter()
+                .map(|var| Self::var(prefix, var))
+                .join("\n"),
+        }
+    }
+
+    fn cat(cmd: &CatCmd) -> String {
+        heredoc(cmd)
+    }
+}
+
+/// Quote a value such that it is safe to `eval` in a POSIX shell.
+///
+/// Values which only contain characters that have no special meaning to the shell are
+/// returned as-is, all other values are single-quoted.
+pub(super) fn quote(value: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-./:@%+,=".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(single_quote(value))
+    }
+}
+
+/// Like `quote`, but for values which need not be valid UTF-8.
+///
+/// POSIX shells support neither ANSI-C quoting nor `\xNN` escapes and so each valid part of
+/// such a value is quoted and each run of invalid bytes is written with `printf` octal escapes
+/// in a command substitution, such as `"$(printf '\351')"`.  Invalid bytes are never
+/// newlines, which a command substitution would strip.
+fn quote_value(value: &OsStr) -> Cow<'_, str> {
+    quote_os(value, quote, |bytes| {
+        bytes
+            .utf8_chunks()
+            .flat_map(|chunk| {
+                let valid = (!chunk.valid().is_empty()).then(|| quote(chunk.valid()).into_owned());
+                let invalid = (!chunk.invalid().is_empty()).then(|| {
+                    let escaped: String = chunk
+                        .invalid()
+                        .iter()
+                        .map(|byte| format!("\\{byte:03o}"))
+                        .collect();
+                    format!("\"$(printf '{escaped}')\"")
+                });
+                valid.into_iter().chain(invalid)
+            })
+            .collect()
+    })
+}
@@ -0,0 +1,69 @@
+use crate::output::{CatCmd, Dialect, Var, ansi_c_quote, full_name, quote_os, single_quote};
+use itertools::Itertools;
+use std::borrow::Cow;
+use std::ffi::OsStr;
+
+/// The zsh shell.
+///
+/// Variables are declared with `typeset -g` so that they are global even when evaluated
+/// inside a function, matching the behaviour of plain assignment in bash.
+pub struct Zsh;
+
+impl Dialect for Zsh {
+    fn var(prefix: &str, var: &Var) -> String {
+        match var {
+            Var::Single(name, value) => {
+                format!(
+                    "typeset -g {}={}",
+                    full_name(prefix, name),
+                    quote_value(value)
+                )
+            }
+            Var::Many(name, values) => format!(
+                "typeset -ga {}=({})",
+                full_name(prefix, name),
+                values.iter().map(|value| quote_value(value)).join(" ")
+            ),
+            Var::Map(name, entries) => format!(
+                "typeset -gA {}=({})",
+                full_name(prefix, name),
+                entries
+                    .iter()
+                    .flat_map(|(key, value)| [quote(key), quote_value(value)])
+                    .join(" ")
+            ),
+            Var::Grouped(..) => var
+                .ungroup()
+                .iter()
+                .map(|var| Self::var(prefix, var))
+                .join("\n"),
+        }
+    }
+
+    fn cat(cmd: &CatCmd) -> String {
+        format!(
+            "print -rn -- {}\nexit {}",
+            single_quote(&cmd.data.ansi().to_string()),
+            cmd.exit_code
+        )
+    }
+}
+
+/// Quote a value such that it is safe to `eval` in zsh.
+///
+/// Unlike bash, a word starting with `=` is subject to `EQUALS` expansion in zsh and so
+/// `=` is not considered safe.
+fn quote(value: &str) -> Cow<'_, str> {
+    let is_safe = |c: char| c.is_ascii_alphanumeric() || "_-./:@%+,".contains(c);
+    if !value.is_empty() && value.chars().all(is_safe) {
+        Cow::Borrowed(value)
+    } else {
+        Cow::Owned(single_quote(value))
+    }
+}
+
+/// Quote an `OsStr` value with `quote` if it is valid UTF-8, and otherwise with the same ANSI-C
+/// quoting as bash, which zsh also supports.
+fn quote_value(value: &OsStr) -> Cow<'_, str> {
+    quote_os(value, quote, ansi_c_quote)
+}
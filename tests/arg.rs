@@ -1,4 +1,5 @@
-use claptrap::command::Command;
+use claptrap::command::{Command, SpecErrorKind};
+use claptrap::output::OutputFormat;
 use claptrap::parse;
 use std::ffi::OsString;
 
@@ -510,6 +511,685 @@ fn test_value_parser() {
     insta::assert_snapshot!(output);
 }
 
+#[test]
+fn test_value_parser_and_typed_value_parser() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            color = { long = "color", value-parser = ["always", "never"], typed-value-parser = "bool" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_typed_value_parser_invalid() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [subcommands.run.args]
+            verbose = { long = "verbose", typed-value-parser = "yes-no" }
+        "#,
+    )
+    .unwrap();
+    let err = clap::Command::try_from(app).unwrap_err();
+    assert_eq!(vec!["prog", "run"], err.command);
+    assert_eq!(Some("verbose"), err.arg.as_deref());
+    assert_eq!("typed-value-parser", err.key);
+    assert_eq!(
+        SpecErrorKind::InvalidValue {
+            value: String::from("yes-no"),
+            expected: &[
+                "bool",
+                "boolish",
+                "falsey",
+                "i8",
+                "i16",
+                "i32",
+                "i64",
+                "u8",
+                "u16",
+                "u32",
+                "u64",
+                "f32",
+                "f64",
+                "path",
+                "non-empty-string",
+                "os-string",
+                "duration",
+                "bytes",
+                "ipv4",
+                "ipv6",
+                "ip",
+                "cidr",
+                "socket-addr",
+                "port",
+                "hostname",
+                "url",
+            ],
+        },
+        err.kind
+    );
+}
+
+#[test]
+fn test_value_parser_possible_value_table() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            protocol = { long = "protocol", help = "The protocol to use", value-parser = [
+                { name = "tcp", help = "Transmission Control Protocol", aliases = ["TCP", "t"] },
+                { name = "udp", help = "User Datagram Protocol" },
+                { name = "icmp", aliases = ["i"], hide = true },
+                "sctp",
+            ] }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--protocol t";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--protocol i";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app.clone(), args2);
+    insta::assert_snapshot!(output2);
+
+    let input3 = "--protocol quic";
+    let args3: Vec<OsString> = input3.split(" ").map(OsString::from).collect();
+    let output3 = parse(app.clone(), args3);
+    insta::assert_snapshot!(output3);
+
+    let input4 = "--help";
+    let args4: Vec<OsString> = input4.split(" ").map(OsString::from).collect();
+    let output4 = parse(app, args4);
+    insta::assert_snapshot!(output4);
+}
+
+#[test]
+fn test_value_parser_possible_value_table_unknown_key() {
+    let err = toml::from_str::<Command>(
+        r#"
+            name = "prog"
+            [args]
+            protocol = { long = "protocol", value-parser = [{ name = "tcp", alias = "t" }] }
+        "#,
+    )
+    .unwrap_err();
+    insta::assert_snapshot!(err.message());
+}
+
+#[test]
+fn test_typed_value_parser_duration() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            timeout = { long = "timeout", typed-value-parser = "duration" }
+            interval = { long = "interval", typed-value-parser = "duration", action = "append" }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--timeout 1h30m --interval 500ms --interval 1.5s --interval 2d";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--timeout 30";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_typed_value_parser_duration_global() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            declare-all = true
+            [args]
+            timeout = { long = "timeout", typed-value-parser = "duration", global = true }
+            [subcommands.run.args]
+            interval = { long = "interval", typed-value-parser = "duration" }
+        "#,
+    )
+    .unwrap();
+
+    let input = "run --timeout 1m";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_typed_value_parser_bytes() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            var-naming = "screaming-snake"
+            [args]
+            maxSize = { long = "max-size", typed-value-parser = "bytes" }
+            blockSize = { long = "block-size", typed-value-parser = "bytes", value-delimiter = ',' }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--max-size 2GiB --block-size 512,1.5kb,4Ki";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--max-size 2GB/s";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_typed_value_parser_network() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            source = { long = "source", typed-value-parser = "ipv4" }
+            gateway = { long = "gateway", typed-value-parser = "ipv6" }
+            dns = { long = "dns", typed-value-parser = "ip", action = "append" }
+            subnet = { long = "subnet", typed-value-parser = "cidr" }
+            target = { long = "target", typed-value-parser = "socket-addr" }
+            port = { long = "port", typed-value-parser = "port" }
+            host = { long = "host", typed-value-parser = "hostname" }
+        "#,
+    )
+    .unwrap();
+    let input = "--source 10.0.0.1 --gateway fe80:0:0::0001 --dns 1.1.1.1 --dns ::1 --subnet 192.168.1.7/24 --target [::1]:8080 --port 443 --host example.com.";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_typed_value_parser_network_absent() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            declare-all = true
+            [args]
+            subnet = { long = "subnet", typed-value-parser = "cidr" }
+            target = { long = "target", typed-value-parser = "socket-addr", action = "append" }
+            endpoint = { long = "endpoint", typed-value-parser = "url" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            subnet = { long = "subnet", typed-value-parser = "cidr" }
+            subnet_prefix = { long = "subnet-prefix" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_typed_value_parser_network_invalid() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            source = { long = "source", typed-value-parser = "ipv4" }
+            subnet = { long = "subnet", typed-value-parser = "cidr" }
+            target = { long = "target", typed-value-parser = "socket-addr" }
+            port = { long = "port", typed-value-parser = "port" }
+            host = { long = "host", typed-value-parser = "hostname" }
+        "#,
+    )
+    .unwrap();
+    let inputs = [
+        "--source 10.0.0.256",
+        "--subnet 10.0.0.0/33",
+        "--target 127.0.0.1",
+        "--port 0",
+        "--host=exa_mple.com",
+    ];
+    for input in inputs {
+        let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+        let output = parse(app.clone(), args);
+        insta::assert_snapshot!(output);
+    }
+}
+
+#[test]
+fn test_typed_value_parser_url() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            endpoint = { long = "endpoint", typed-value-parser = "url" }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--endpoint https://example.com/api/v1?page=2#top";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--endpoint example.com/api";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_value_pattern() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            version = { long = "version", value-pattern = '^[0-9]+\.[0-9]+\.[0-9]+$' }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--version 1.2.3";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--version 1.2";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_value_glob() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            branch = { long = "branch", value-glob = "release/*", action = "append" }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--branch release/1.0 --branch release/2.0/rc1";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--branch main";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_value_pattern_invalid() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            version = { long = "version", value-pattern = "[0-9" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_value_glob_with_value_parser() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            branch = { long = "branch", value-glob = "release/*", value-parser = ["main"] }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_path_must_exist() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("input.txt");
+    std::fs::write(&file, "").unwrap();
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            input = { long = "input", path = { must-exist = true, kind = "file", readable = true } }
+        "#,
+    )
+    .unwrap();
+    let tmp = dir.path().to_str().unwrap();
+
+    let args1: Vec<OsString> = vec!["--input".into(), file.into()];
+    let output1 = parse(app.clone(), args1).to_string().replace(tmp, "[TMP]");
+    insta::assert_snapshot!(output1);
+
+    let args2: Vec<OsString> = vec!["--input".into(), dir.path().join("missing.txt").into()];
+    let output2 = parse(app.clone(), args2).to_string().replace(tmp, "[TMP]");
+    insta::assert_snapshot!(output2);
+
+    let args3: Vec<OsString> = vec!["--input".into(), dir.path().into()];
+    let output3 = parse(app, args3).to_string().replace(tmp, "[TMP]");
+    insta::assert_snapshot!(output3);
+}
+
+#[test]
+fn test_path_writable() {
+    let dir = tempfile::tempdir().unwrap();
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            output = { long = "output", path = { kind = "file", writable = true } }
+        "#,
+    )
+    .unwrap();
+    let tmp = dir.path().to_str().unwrap();
+
+    let args1: Vec<OsString> = vec!["--output".into(), dir.path().join("output.txt").into()];
+    let output1 = parse(app.clone(), args1).to_string().replace(tmp, "[TMP]");
+    insta::assert_snapshot!(output1);
+
+    let missing = dir.path().join("missing").join("output.txt");
+    let args2: Vec<OsString> = vec!["--output".into(), missing.into()];
+    let output2 = parse(app, args2).to_string().replace(tmp, "[TMP]");
+    insta::assert_snapshot!(output2);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_path_executable() {
+    use std::os::unix::fs::PermissionsExt;
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("script.sh");
+    std::fs::write(&script, "").unwrap();
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o644)).unwrap();
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            script = { long = "script", path = { executable = true } }
+        "#,
+    )
+    .unwrap();
+    let tmp = dir.path().to_str().unwrap();
+
+    let args1: Vec<OsString> = vec!["--script".into(), script.clone().into()];
+    let output1 = parse(app.clone(), args1).to_string().replace(tmp, "[TMP]");
+    insta::assert_snapshot!(output1);
+
+    std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let args2: Vec<OsString> = vec!["--script".into(), script.into()];
+    let output2 = parse(app, args2).to_string().replace(tmp, "[TMP]");
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_path_canonicalize() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            dir = { long = "dir", path = { kind = "dir", canonicalize = true } }
+        "#,
+    )
+    .unwrap();
+    let root = env!("CARGO_MANIFEST_DIR");
+
+    let input1 = "--dir tests/resources/../snapshots";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1)
+        .to_string()
+        .replace(root, "[ROOT]");
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--dir tests/missing";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2).to_string().replace(root, "[ROOT]");
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_path_with_value_glob() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            config = { long = "config", value-glob = "*.toml", path = { must-exist = true } }
+        "#,
+    )
+    .unwrap();
+    let input1 = "--config tests/resources/myapp.toml";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--config tests/resources/bash_file.sh";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app.clone(), args2);
+    insta::assert_snapshot!(output2);
+
+    let input3 = "--config tests/resources/missing.toml";
+    let args3: Vec<OsString> = input3.split(" ").map(OsString::from).collect();
+    let output3 = parse(app, args3);
+    insta::assert_snapshot!(output3);
+}
+
+#[test]
+fn test_typed_value_parser_bool() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            color = { long = "color", typed-value-parser = "bool" }
+        "#,
+    )
+    .unwrap();
+    let input = "--color true";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_typed_value_parser_integers() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            port = { long = "port", typed-value-parser = "u16", min-value = 1 }
+            offset = { long = "offset", typed-value-parser = "i8", allow-negative-numbers = true }
+            ids = { long = "id", typed-value-parser = "u64", action = "append" }
+        "#,
+    )
+    .unwrap();
+    let input = "--port 0443 --offset -12 --id 1 --id +2";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_typed_value_parser_integer_range() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            level = { long = "level", typed-value-parser = "i32", min-value = -1, max-value = 5 }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--level 6";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--level high";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_typed_value_parser_integer_type_range() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            port = { long = "port", typed-value-parser = "u16" }
+        "#,
+    )
+    .unwrap();
+    let input = "--port 65536";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_typed_value_parser_unsigned_negative_bound() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            port = { long = "port", typed-value-parser = "u16", min-value = -1 }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_typed_value_parser_bound_without_integer() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            ratio = { long = "ratio", typed-value-parser = "f64", max-value = 1 }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_typed_value_parser_float() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            ratio = { long = "ratio", typed-value-parser = "f64" }
+            scale = { long = "scale", typed-value-parser = "f32" }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--ratio 0.50 --scale 1e3";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--ratio half";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_typed_value_parser_path() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            config = { long = "config", typed-value-parser = "path" }
+        "#,
+    )
+    .unwrap();
+    let input = "--config ./etc/prog.toml";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_typed_value_parser_non_empty_string() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            name = { long = "name", typed-value-parser = "non-empty-string" }
+        "#,
+    )
+    .unwrap();
+
+    let args1: Vec<OsString> = vec!["--name".into(), "x".into()];
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let args2: Vec<OsString> = vec!["--name".into(), "".into()];
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_typed_value_parser_os_string() {
+    use std::os::unix::ffi::OsStrExt;
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            name = { long = "name", typed-value-parser = "os-string" }
+            file = { long = "file", typed-value-parser = "path", action = "append" }
+            text = { long = "text" }
+        "#,
+    )
+    .unwrap();
+    let non_utf8 = |bytes: &[u8]| OsString::from(std::ffi::OsStr::from_bytes(bytes));
+
+    let args1 = vec![
+        "--name".into(),
+        non_utf8(b"caf\xe9 'bar'"),
+        "--file".into(),
+        non_utf8(b"\xff\xfe.txt"),
+        "--file".into(),
+        "ok.txt".into(),
+    ];
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+    insta::assert_snapshot!(output1.render(OutputFormat::Zsh));
+    insta::assert_snapshot!(output1.render(OutputFormat::Fish));
+    insta::assert_snapshot!(output1.render(OutputFormat::Posix));
+    insta::assert_snapshot!(output1.render(OutputFormat::Json));
+
+    let args2 = vec!["--text".into(), non_utf8(b"caf\xe9")];
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
 #[test]
 fn test_num_args() {
     let app: Command = toml::from_str(
@@ -526,6 +1206,20 @@ fn test_num_args() {
     insta::assert_snapshot!(output);
 }
 
+#[test]
+fn test_num_args_and_min_max_args() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            mode = { long = "mode", num-args = 1, min-args = 1, max-args = 2 }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
 #[test]
 fn test_num_args_default_missing_value() {
     let app: Command = toml::from_str(r#"
@@ -556,6 +1250,7 @@ fn test_num_args_tuples() {
             name = "prog"
             [args]
             file = { short = 'F', num-args = 2, action = "set" }
+            pair = { long = "pair", num-args = 2, action = "append" }
         "#,
     )
     .unwrap();
@@ -567,8 +1262,20 @@ fn test_num_args_tuples() {
 
     let input2 = "-F file1";
     let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
-    let output2 = parse(app, args2);
+    let output2 = parse(app.clone(), args2);
     insta::assert_snapshot!(output2);
+
+    let input3 = "--pair a b --pair c d";
+    let args3: Vec<OsString> = input3.split(" ").map(OsString::from).collect();
+    let output3 = parse(app.clone(), args3);
+    insta::assert_snapshot!(output3);
+    insta::assert_snapshot!(output3.render(OutputFormat::Json));
+    insta::assert_snapshot!(output3.render(OutputFormat::Posix));
+
+    let input4 = "--pair a";
+    let args4: Vec<OsString> = input4.split(" ").map(OsString::from).collect();
+    let output4 = parse(app, args4);
+    insta::assert_snapshot!(output4);
 }
 
 #[test]
@@ -1170,10 +1877,127 @@ fn test_hide_long_help() {
     insta::assert_snapshot!(output2);
 }
 
-// TODO: group
-// TODO: groups
-// TODO: default_value_if
-// TODO: default_value_ifs
+#[test]
+fn test_group() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            json = { long = "json", action = "set-true", group = "format" }
+            yaml = { long = "yaml", action = "set-true", group = "format" }
+        "#,
+    )
+    .unwrap();
+    let input = "--json";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_groups() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            json = { long = "json", action = "set-true", groups = ["format", "machine"] }
+            yaml = { long = "yaml", action = "set-true", groups = ["format", "human"] }
+        "#,
+    )
+    .unwrap();
+    let input = "--yaml";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_default_value_if() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            tls = { long = "tls", action = "set-true" }
+            port = { long = "port", default-value = "80", default-value-if = { arg = "tls", value = "true", default = "443" } }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--tls";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let args2: Vec<OsString> = vec![];
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_default_value_if_present() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            cert = { long = "cert" }
+            mode = { long = "mode", default-value = "plain", default-value-if = { arg = "cert", default = "tls" } }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--cert server.pem";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let args2: Vec<OsString> = vec![];
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_default_value_if_no_default() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            flag = { long = "flag", action = "set-true" }
+            other = { long = "other", default-value = "default", default-value-if = { arg = "flag", value = "true" } }
+        "#,
+    )
+    .unwrap();
+    let input = "--flag";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_default_value_ifs() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            flag = { long = "flag", action = "set" }
+            opt = { long = "opt", action = "set" }
+            other = { long = "other", default-value-ifs = [
+                { arg = "flag", value = "true", default = "default" },
+                { arg = "opt", value = "channal", default = "chan" },
+            ] }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--opt channal";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--flag true";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
 
 #[test]
 fn test_required_unless_present() {
@@ -1243,11 +2067,132 @@ fn test_required_unless_present_any() {
     insta::assert_snapshot!(output2);
 }
 
-// TODO: required_if_eq
-// TODO: required_if_eq_any
-// TODO: required_if_eq_all
-// TODO: requires_if
-// TODO: requires_ifs
+#[test]
+fn test_required_if_eq() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            mode = { long = "mode" }
+            cert = { long = "cert", required-if-eq = { arg = "mode", value = "tls" } }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--mode plain";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--mode tls";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_required_if_eq_any() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            cfg = { long = "config", required-if-eq-any = [
+                { arg = "extra", value = "val" },
+                { arg = "option", value = "spec" },
+            ] }
+            extra = { long = "extra" }
+            option = { long = "option" }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--option other";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--option spec";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_required_if_eq_all() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            cfg = { long = "config", required-if-eq-all = [
+                { arg = "extra", value = "val" },
+                { arg = "option", value = "spec" },
+            ] }
+            extra = { long = "extra" }
+            option = { long = "option" }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--option spec";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--extra val --option spec";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_requires_if() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            mode = { long = "mode", requires-if = { value = "tls", arg = "cert" } }
+            cert = { long = "cert" }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--mode plain";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--mode tls";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
+
+#[test]
+fn test_requires_ifs() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            config = { long = "config", requires-ifs = [
+                { value = "special.conf", arg = "opt" },
+                { arg = "other" },
+            ] }
+            opt = { long = "option" }
+            other = { long = "other" }
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--config special.conf --other x";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--config other.conf --other x";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app, args2);
+    insta::assert_snapshot!(output2);
+}
 
 #[test]
 fn test_conflicts_with() {
@@ -1,4 +1,5 @@
-use claptrap::command::Command;
+use claptrap::command::{Command, Diagnostic, SpecSource, render_check, render_diagnostics};
+use claptrap::output::{Output, OutputFormat, VarNaming};
 use claptrap::parse;
 use std::ffi::OsString;
 
@@ -55,3 +56,722 @@ fn it_outputs_error_and_exit_1_on_unexpected_arg() {
     let output = parse(app, args);
     insta::assert_snapshot!(output);
 }
+
+#[test]
+fn it_outputs_quoted_variables() {
+    let spec = include_str!("resources/myapp.toml");
+    let app: Command = toml::from_str(spec).unwrap();
+    let args: Vec<OsString> = vec![
+        "--mode".into(),
+        "$(echo pwned)".into(),
+        "-p".into(),
+        "it's udp".into(),
+    ];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_zsh_variables() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            mode = { short = "m", long = "mode" }
+            files = { action = "append" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec![
+        "--mode".into(),
+        "=stream".into(),
+        "it's".into(),
+        "a b".into(),
+    ];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output.render(OutputFormat::Zsh));
+}
+
+#[test]
+fn it_outputs_zsh_usage_and_exit_2_on_no_args() {
+    let spec = include_str!("resources/myapp.toml");
+    let app: Command = toml::from_str(spec).unwrap();
+    let args: Vec<OsString> = vec![];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output.render(OutputFormat::Zsh));
+}
+
+#[test]
+fn it_outputs_fish_variables() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            mode = { short = "m", long = "mode" }
+            files = { action = "append" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec![
+        "--mode".into(),
+        "%self".into(),
+        "it's".into(),
+        "back\\slash".into(),
+    ];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output.render(OutputFormat::Fish));
+}
+
+#[test]
+fn it_outputs_fish_usage_and_exit_2_on_no_args() {
+    let spec = include_str!("resources/myapp.toml");
+    let app: Command = toml::from_str(spec).unwrap();
+    let args: Vec<OsString> = vec![];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output.render(OutputFormat::Fish));
+}
+
+#[test]
+fn it_outputs_posix_variables() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            mode = { short = "m", long = "mode" }
+            files = { action = "append" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec![
+        "--mode".into(),
+        "stream".into(),
+        "it's".into(),
+        "a b".into(),
+    ];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output.render(OutputFormat::Posix));
+}
+
+#[test]
+fn it_outputs_json_variables() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            mode = { short = "m", long = "mode" }
+            files = { short = "f", long = "file", action = "append" }
+            [subcommands.env.subcommands.create.args]
+            name = { long = "name" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec![
+        "--mode".into(),
+        "it's \"json\"".into(),
+        "-f".into(),
+        "a".into(),
+        "-f".into(),
+        "b".into(),
+        "env".into(),
+        "create".into(),
+        "--name".into(),
+        "x".into(),
+    ];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output.render(OutputFormat::Json));
+}
+
+#[test]
+fn it_outputs_json_usage_and_exit_2_on_no_args() {
+    let spec = include_str!("resources/myapp.toml");
+    let app: Command = toml::from_str(spec).unwrap();
+    let args: Vec<OsString> = vec![];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output.render(OutputFormat::Json));
+}
+
+#[test]
+fn it_outputs_assoc_array() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            assoc-array = true
+            [args]
+            mode = { short = "m", long = "mode", default-value = "stream" }
+            port = { short = "p", long = "port" }
+            files = { short = "f", long = "file", action = "append" }
+            [subcommands.env.args]
+            name = { long = "name", default-value = "dev" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec![
+        "-p".into(),
+        "it's 443".into(),
+        "-f".into(),
+        "a".into(),
+        "-f".into(),
+        "b".into(),
+        "env".into(),
+    ];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_zsh_assoc_array() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            mode = { short = "m", long = "mode", default-value = "stream" }
+            port = { short = "p", long = "port" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec!["-p".into(), "it's 443".into()];
+    let output = parse(app.assoc_array(true), args);
+    insta::assert_snapshot!(output.render(OutputFormat::Zsh));
+}
+
+#[test]
+fn it_outputs_posix_assoc_array() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            mode = { short = "m", long = "mode", default-value = "stream" }
+            port = { short = "p", long = "port" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec!["-p".into(), "it's 443".into()];
+    let output = parse(app.assoc_array(true), args);
+    insta::assert_snapshot!(output.render(OutputFormat::Posix));
+}
+
+#[test]
+fn it_outputs_var_prefix() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            var-prefix = "opt"
+            [args]
+            mode = { short = "m", long = "mode" }
+            dry-run = { long = "dry-run", action = "set-true" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec!["--mode".into(), "tls".into(), "--dry-run".into()];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_snake_case_names() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            var-naming = "snake-case"
+            [args]
+            dryRun = { long = "dry-run", action = "set-true" }
+            log-level = { long = "log-level" }
+            [subcommands.create-env.args]
+            envName = { long = "name" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec![
+        "--dry-run".into(),
+        "--log-level".into(),
+        "debug".into(),
+        "create-env".into(),
+        "--name".into(),
+        "dev".into(),
+    ];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_screaming_snake_names() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            dryRun = { long = "dry-run", action = "set-true" }
+            log-level = { long = "log-level" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec!["--dry-run".into(), "--log-level".into(), "debug".into()];
+    let output = parse(
+        app.var_prefix("myApp")
+            .var_naming(VarNaming::ScreamingSnake),
+        args,
+    );
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_value_sources() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            value-source = true
+            [args]
+            color = { long = "color", default-value = "auto" }
+            level = { long = "level", env = "MYAPP_VALUE_SOURCE_LEVEL" }
+            name = { long = "name" }
+            verbose = { long = "verbose", action = "set-true" }
+            missing = { long = "missing" }
+            [subcommands.run.args]
+            jobs = { long = "jobs", default-value = "1" }
+        "#,
+    )
+    .unwrap();
+    unsafe {
+        std::env::set_var("MYAPP_VALUE_SOURCE_LEVEL", "debug");
+    }
+    let args: Vec<OsString> = vec!["--name".into(), "x".into(), "run".into()];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_json_value_sources() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            color = { long = "color", default-value = "auto" }
+            name = { long = "name" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec!["--name".into(), "x".into()];
+    let output = parse(app.value_source(true), args);
+    insta::assert_snapshot!(output.render(OutputFormat::Json));
+}
+
+#[test]
+fn it_outputs_error_on_value_source_var_name_collision() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            value-source = true
+            [args]
+            color = { long = "color" }
+            color_source = { long = "color-source" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_indices_and_order() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            indices = true
+            [args]
+            include = { long = "include", action = "append" }
+            exclude = { long = "exclude", action = "append" }
+            pair = { long = "pair", num-args = 2, action = "append" }
+            verbose = { short = 'v', action = "set-true" }
+            color = { long = "color", default-value = "auto" }
+            [subcommands.run.args]
+            jobs = { long = "jobs" }
+            dry-run = { long = "dry-run", action = "set-true" }
+        "#,
+    )
+    .unwrap();
+    let input = "--include a --pair b c --exclude d -v --include=e run --dry-run --jobs 2";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_error_on_order_var_name_collision() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            order = { long = "order" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app.indices(true), vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_declares_absent_args() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            declare-all = true
+            value-source = true
+            [args]
+            mode = { long = "mode" }
+            files = { long = "file", action = "append" }
+            point = { long = "point", num-args = 2 }
+            timeout = { long = "timeout", typed-value-parser = "duration" }
+            verbose = { short = "v", action = "count" }
+            quiet = { long = "quiet", action = "set-true" }
+            input = { index = 1 }
+            json = { long = "json", action = "set-true", group = "format" }
+            [subcommands.run.args]
+            jobs = { long = "jobs" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec!["--mode".into(), "tls".into()];
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_declares_absent_args_of_subcommand() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            mode = { long = "mode" }
+            [subcommands.run.args]
+            jobs = { long = "jobs" }
+            [subcommands.run.subcommands.now]
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec!["run".into()];
+    let output = parse(app.declare_all(true), args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_error_on_var_name_collision() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            dry-run = { long = "dry-run", action = "set-true" }
+            dry_run = { long = "dry_run", action = "set-true" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_error_on_subcommand_var_name_collision() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            env_name = { long = "env-name" }
+            [subcommands.env.args]
+            name = { long = "name" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_error_on_count_var_name_collision() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            files = { long = "file", action = "append" }
+            files_count = { long = "files-count" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app.format(OutputFormat::Posix), vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_allows_count_var_names_outside_posix() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            files = { long = "file", action = "append" }
+            files_count = { long = "files-count" }
+        "#,
+    )
+    .unwrap();
+    let args: Vec<OsString> = vec![
+        "--file".into(),
+        "a".into(),
+        "--file".into(),
+        "b".into(),
+        "--files-count".into(),
+        "2".into(),
+    ];
+    let output = parse(app.format(OutputFormat::Bash), args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_error_on_occurrence_var_name_collision() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            pair = { long = "pair", action = "append", num-args = 2 }
+            pair_0 = { long = "pair-0" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_error_on_assoc_array_var_name_collision() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            count = { long = "count" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app.assoc_array(true).format(OutputFormat::Posix), vec![]);
+    insta::assert_snapshot!(output);
+    // Outside POSIX shells the associative array is not output with derived variables.
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            count = { long = "count" }
+            0 = { long = "zero" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app.assoc_array(true).format(OutputFormat::Bash), vec![]);
+    assert!(matches!(output, Output::Variables(_)));
+}
+
+#[test]
+fn it_outputs_error_on_invalid_var_prefix() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            var-prefix = "my-app"
+            [args]
+            mode = { long = "mode" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_reports_all_unknown_id_references() {
+    let spec = r#"
+name = "myapp"
+[args]
+mode = { long = "mode", requires = "prot", conflicts-with-all = ["verbose", "quiet"] }
+port = { long = "port", id = "prt", overrides-with = "port" }
+verbose = { long = "verbose", action = "set-true", global = true }
+[groups.output]
+args = ["verbose", "silent"]
+[subcommands.run.args]
+dry-run = { long = "dry-run", requires = "verbose", required-unless-present-any = ["mod"] }
+"#;
+    let diagnostics = Command::from_toml(spec).unwrap_err();
+    let source = SpecSource {
+        path: "myapp.toml",
+        text: spec,
+    };
+    insta::assert_snapshot!(render_diagnostics(&diagnostics, Some(source)).ansi());
+}
+
+#[test]
+fn it_reports_toml_errors_with_location() {
+    let spec = r#"
+name = "myapp"
+[args]
+mode = { long = "mode", unknown = true }
+"#;
+    let diagnostics = Command::from_toml(spec).unwrap_err();
+    let source = SpecSource {
+        path: "myapp.toml",
+        text: spec,
+    };
+    insta::assert_snapshot!(render_diagnostics(&diagnostics, Some(source)).ansi());
+}
+
+#[test]
+fn it_reports_conditional_toml_errors_with_location() {
+    let spec = r#"
+name = "myapp"
+[args]
+tls = { long = "tls", action = "set-true" }
+port = { long = "port", default-value-if = { arg = "tls", value = true, default = "443" } }
+"#;
+    let diagnostics = Command::from_toml(spec).unwrap_err();
+    let source = SpecSource {
+        path: "myapp.toml",
+        text: spec,
+    };
+    insta::assert_snapshot!(render_diagnostics(&diagnostics, Some(source)).ansi());
+}
+
+#[test]
+fn it_outputs_error_on_unknown_id_reference() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            mode = { long = "mode", requires = "prot" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_error_on_missing_name() {
+    let app: Command = toml::from_str(
+        r#"
+            [args]
+            mode = { long = "mode" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_outputs_error_on_duplicate_index() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            input = {}
+            output = { index = 1 }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn it_checks_spec_missing_name() {
+    let spec = r#"[args]
+mode = { long = "mode" }
+"#;
+    let app: Command = toml::from_str(spec).unwrap();
+    let source = SpecSource {
+        path: "myapp.toml",
+        text: spec,
+    };
+    assert_eq!(
+        "myapp.toml:1:1: missing-name: the root command has no `name`\n",
+        render_check(&app.check(), source, OutputFormat::Bash)
+    );
+}
+
+#[test]
+fn it_checks_a_valid_spec() {
+    let spec = include_str!("resources/myapp.toml");
+    let app: Command = toml::from_str(spec).unwrap();
+    assert_eq!(Vec::<Diagnostic>::new(), app.check());
+}
+
+#[test]
+fn it_checks_spec_lints() {
+    let spec = r#"
+name = "myapp"
+help-expected = true
+[args]
+host = { short = 'h', long = "host", help = "The host" }
+dry-run = { long = "dry-run", action = "set-true", help = "Dry run" }
+files = { min-args = 1, help = "The files" }
+out = { help = "The output" }
+extra = {}
+[subcommands.run.args]
+mode = { long = "mode", requires = "prot" }
+"#;
+    let app: Command = toml::from_str(spec).unwrap();
+    let source = SpecSource {
+        path: "myapp.toml",
+        text: spec,
+    };
+    insta::assert_snapshot!(render_check(&app.check(), source, OutputFormat::Bash));
+}
+
+#[test]
+fn it_checks_spec_duplicate_flags() {
+    let spec = r#"
+name = "myapp"
+[args]
+host = { short = 'o', long = "host", action = "set" }
+output = { short = 'o', long = "output", aliases = ["host"], action = "set" }
+"#;
+    let app: Command = toml::from_str(spec).unwrap();
+    let source = SpecSource {
+        path: "myapp.toml",
+        text: spec,
+    };
+    let report = render_check(&app.check(), source, OutputFormat::Bash);
+    assert!(report.contains(
+        "duplicate-flag: `-o` of command `myapp` is used by both arg `host` and arg `output`"
+    ));
+    assert!(report.contains(
+        "duplicate-flag: `--host` of command `myapp` is used by both arg `host` and arg `output`"
+    ));
+}
+
+#[test]
+fn it_checks_spec_positionals_as_json() {
+    let spec = r#"
+name = "myapp"
+version = "1.0.0"
+[args]
+verbose = { short = 'V', action = "set-true" }
+input = {}
+output = { required = true }
+extra = { last = true }
+rest = { last = true }
+"#;
+    let app: Command = toml::from_str(spec).unwrap();
+    let source = SpecSource {
+        path: "myapp.toml",
+        text: spec,
+    };
+    insta::assert_snapshot!(render_check(&app.check(), source, OutputFormat::Json));
+}
+
+#[test]
+fn it_checks_spec_positional_indices() {
+    let spec = r#"
+name = "myapp"
+[args]
+mode = { index = 2 }
+[subcommands.run.args]
+input = {}
+output = { index = 1 }
+"#;
+    let app: Command = toml::from_str(spec).unwrap();
+    let source = SpecSource {
+        path: "myapp.toml",
+        text: spec,
+    };
+    insta::assert_snapshot!(render_check(&app.check(), source, OutputFormat::Bash));
+}
@@ -19,10 +19,155 @@ fn test_arg() {
     insta::assert_snapshot!(output);
 }
 
-// TODO: group
-// TODO: groups
-// TODO: subcommand
-// TODO: subcommands
+#[test]
+fn test_group() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            json = { long = "json", action = "set-true" }
+            yaml = { long = "yaml", action = "set-true" }
+            text = { long = "text", action = "set-true" }
+            [groups.format]
+            args = ["json", "yaml", "text"]
+            required = true
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--yaml";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--json --yaml";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app.clone(), args2);
+    insta::assert_snapshot!(output2);
+
+    let args3: Vec<OsString> = vec![];
+    let output3 = parse(app, args3);
+    insta::assert_snapshot!(output3);
+}
+
+#[test]
+fn test_groups() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "prog"
+            [args]
+            major = { long = "major", action = "set-true" }
+            minor = { long = "minor", action = "set-true" }
+            patch = { long = "patch", action = "set-true" }
+            spec = { long = "spec" }
+            input = { long = "input" }
+            [groups.vers]
+            args = ["major", "minor", "patch", "spec"]
+            multiple = true
+            requires = "input"
+            conflicts-with = "output"
+            [groups.output]
+            args = ["json"]
+            [args.json]
+            long = "json"
+            action = "set-true"
+        "#,
+    )
+    .unwrap();
+
+    let input1 = "--major --patch --input file";
+    let args1: Vec<OsString> = input1.split(" ").map(OsString::from).collect();
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "--minor";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app.clone(), args2);
+    insta::assert_snapshot!(output2);
+
+    let input3 = "--minor --input file --json";
+    let args3: Vec<OsString> = input3.split(" ").map(OsString::from).collect();
+    let output3 = parse(app, args3);
+    insta::assert_snapshot!(output3);
+}
+
+#[test]
+fn test_subcommand() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "deploy"
+            [args]
+            verbose = { short = 'v', long = "verbose", action = "set-true" }
+            [subcommands.env]
+            about = "Manage environments"
+            [subcommands.env.subcommands.create]
+            about = "Create an environment"
+            [subcommands.env.subcommands.create.args]
+            name = { long = "name", required = true }
+            [subcommands.env.subcommands.delete]
+            about = "Delete an environment"
+        "#,
+    )
+    .unwrap();
+    let input = "-v env create --name x";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_subcommand_args() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "deploy"
+            [args]
+            region = { long = "region" }
+            [subcommands.env.args]
+            region = { long = "region" }
+            files = { action = "append" }
+        "#,
+    )
+    .unwrap();
+    let input = "--region eu env --region us a b";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_subcommand_name() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "deploy"
+            [subcommands.env]
+            name = "environment"
+        "#,
+    )
+    .unwrap();
+    let input = "environment";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
+#[test]
+fn test_subcommand_help() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "deploy"
+            [subcommands.env]
+            about = "Manage environments"
+            [subcommands.env.subcommands.create]
+            about = "Create an environment"
+        "#,
+    )
+    .unwrap();
+    let input = "env --help";
+    let args: Vec<OsString> = input.split(" ").map(OsString::from).collect();
+    let output = parse(app, args);
+    insta::assert_snapshot!(output);
+}
+
 // TODO: error
 
 #[test]
@@ -622,6 +767,70 @@ fn test_allow_missing_positional_style_2() {
     insta::assert_snapshot!(output2);
 }
 
+#[test]
+fn test_allow_external_subcommands() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "mytool"
+            allow-external-subcommands = true
+            [args]
+            verbose = { short = 'v', action = "set-true" }
+            [subcommands.build]
+        "#,
+    )
+    .unwrap();
+
+    let args1: Vec<OsString> = vec![
+        "-v".into(),
+        "foo".into(),
+        "--bar".into(),
+        "baz qux".into(),
+        "-v".into(),
+    ];
+    let output1 = parse(app.clone(), args1);
+    insta::assert_snapshot!(output1);
+
+    let input2 = "foo";
+    let args2: Vec<OsString> = input2.split(" ").map(OsString::from).collect();
+    let output2 = parse(app.clone(), args2);
+    insta::assert_snapshot!(output2);
+
+    let input3 = "build";
+    let args3: Vec<OsString> = input3.split(" ").map(OsString::from).collect();
+    let output3 = parse(app.clone(), args3);
+    insta::assert_snapshot!(output3);
+
+    let output4 = parse(app.declare_all(true), vec![]);
+    insta::assert_snapshot!(output4);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_external_subcommand_value_parser() {
+    use std::os::unix::ffi::OsStrExt;
+    let spec = |value_parser: &str| {
+        toml::from_str::<Command>(&format!(
+            r#"
+                name = "mytool"
+                allow-external-subcommands = true
+                external-subcommand-value-parser = "{value_parser}"
+            "#
+        ))
+        .unwrap()
+    };
+    let args =
+        || -> Vec<OsString> { vec!["foo".into(), std::ffi::OsStr::from_bytes(b"caf\xe9").into()] };
+
+    let output1 = parse(spec("os-string"), args());
+    insta::assert_snapshot!(output1);
+
+    let output2 = parse(spec("non-empty-string"), args());
+    insta::assert_snapshot!(output2);
+
+    let output3 = parse(spec("text"), args());
+    insta::assert_snapshot!(output3);
+}
+
 // TODO subcommand related
 // short_flag
 // long_flag
@@ -640,8 +849,6 @@ fn test_allow_missing_positional_style_2() {
 // display_order
 // hide
 // subcommand_required
-// allow_external_subcommands
-// external_subcommand_value_parser
 // args_conflicts_with_subcommands
 // subcommand_precedence_over_arg
 // subcommand_negates_reqs
@@ -5,7 +5,7 @@ set -euo pipefail
 eval "$($CLAPTRAP_BIN --spec - -- "$@" <<'SPEC'
   name = "myapp"
   [args]
-  # this wil cause clap to panic
+  # clap would panic on this, so it is reported as an error in the spec
   mode = { index = 2 }
 SPEC
 )"
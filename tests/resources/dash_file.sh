@@ -0,0 +1,8 @@
+#!/usr/bin/env dash
+
+set -eu
+
+eval "$($CLAPTRAP_BIN --spec tests/resources/myapp.toml --format posix -- "$@")"
+
+echo "mode: $claptrap_mode"
+echo "protocol: $claptrap_protocol"
@@ -0,0 +1,6 @@
+#!/usr/bin/env fish
+
+eval ($CLAPTRAP_BIN --spec tests/resources/myapp.toml --format fish -- $argv | string collect)
+
+echo "mode: $claptrap_mode"
+echo "protocol: $claptrap_protocol"
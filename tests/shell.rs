@@ -1,5 +1,13 @@
 #![cfg(not(windows))]
 
+use claptrap::command::Command;
+use claptrap::output::{Output, OutputFormat};
+use claptrap::parse;
+use itertools::Itertools;
+use std::ffi::{OsStr, OsString};
+use std::fmt::Display;
+use std::os::unix::ffi::OsStrExt;
+
 const CLAPTRAP_BIN: &str = env!("CARGO_BIN_EXE_claptrap");
 
 #[test]
@@ -60,6 +68,623 @@ fn bash_panic() {
         .env("CLAPTRAP_BIN", CLAPTRAP_BIN)
         .output()
         .expect("Failed to execute command");
-    assert_eq!(Some(3), output.status.code());
+    assert_eq!(Some(1), output.status.code());
+    insta::assert_snapshot!(String::from_utf8_lossy(&output.stdout));
+}
+
+/// Values which would be word split, expanded or executed if they were not quoted.
+const HOSTILE_VALUES: &[&str] = &[
+    "",
+    "a b",
+    "  leading and trailing  ",
+    "$(echo pwned)",
+    "`echo pwned`",
+    "${HOME}",
+    "; echo pwned",
+    "&& echo pwned",
+    "| cat",
+    "it's",
+    "'",
+    "''",
+    "a''b",
+    "\"double\"",
+    "back\\slash",
+    "*",
+    "~",
+    "a:~",
+    "line1\nline2",
+    "tab\there",
+    "#comment",
+    "!!",
+    "-n",
+    "--help",
+    "%self",
+    "(a b)",
+    "=ls",
+    "ünïcödé",
+];
+
+/// Values which are not valid UTF-8, mixed with characters which must be quoted.
+const NON_UTF8_VALUES: &[&[u8]] = &[
+    b"caf\xe9",
+    b"\xff\xfe",
+    b"\xc3",
+    b"it's \xe9",
+    b"back\\slash\xff",
+    b"\xe9\n",
+    b"$(echo pwned)\xff",
+];
+
+#[test]
+fn bash_eval_quotes_single_values() {
+    let spec = r#"
+        name = "myapp"
+        [args]
+        value = { long = "value", allow-hyphen-values = true }
+    "#;
+    for &value in HOSTILE_VALUES {
+        let app: Command = toml::from_str(spec).unwrap();
+        let output = parse(app, vec!["--value".into(), value.into()]);
+        let stdout = bash_eval(&output, r#"printf '%s' "$claptrap_value""#);
+        assert_eq!(value, stdout, "value {value:?} was not preserved");
+    }
+}
+
+#[test]
+fn bash_eval_quotes_many_values() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            values = { long = "values", action = "append", allow-hyphen-values = true }
+        "#,
+    )
+    .unwrap();
+    let args = HOSTILE_VALUES
+        .iter()
+        .flat_map(|&value| ["--values".into(), OsString::from(value)])
+        .collect();
+    let output = parse(app, args);
+    let stdout = bash_eval(&output, r#"printf '%s\0' "${claptrap_values[@]}""#);
+    let values = stdout.split_terminator('\0').collect::<Vec<_>>();
+    assert_eq!(HOSTILE_VALUES, values.as_slice());
+}
+
+#[test]
+fn bash_eval_non_utf8_values() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            values = { long = "values", action = "append", typed-value-parser = "os-string" }
+        "#,
+    )
+    .unwrap();
+    let args = NON_UTF8_VALUES
+        .iter()
+        .flat_map(|&value| ["--values".into(), OsStr::from_bytes(value).to_os_string()])
+        .collect();
+    let output = parse(app, args);
+    let stdout = bash_eval(
+        &output,
+        r#"
+            printf '%s\0' "${claptrap_values[@]}" | od -An -tx1 -v
+        "#,
+    );
+    assert_eq!(
+        hex_values(NON_UTF8_VALUES),
+        stdout.split_whitespace().join(" ")
+    );
+}
+
+#[test]
+fn bash_eval_assoc_array() {
+    let spec = r#"
+        name = "myapp"
+        assoc-array = true
+        [args]
+        value = { long = "value", allow-hyphen-values = true }
+        mode = { long = "mode", default-value = "stream" }
+    "#;
+    for &value in HOSTILE_VALUES {
+        let app: Command = toml::from_str(spec).unwrap();
+        let output = parse(app, vec!["--value".into(), value.into()]);
+        let stdout = bash_eval(
+            &output,
+            r#"printf '%s\0' "${claptrap[value]}" "${claptrap[mode]}" "${claptrap_present[@]}""#,
+        );
+        let values = stdout.split_terminator('\0').collect::<Vec<_>>();
+        assert_eq!(
+            vec![value, "stream", "value"],
+            values,
+            "value {value:?} was not preserved"
+        );
+    }
+}
+
+#[test]
+fn bash_eval_assoc_array_inside_function() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            assoc-array = true
+            [args]
+            mode = { long = "mode" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec!["--mode".into(), "tls".into()]);
+    let stdout = bash_eval(
+        &output,
+        r#"unset claptrap; f() { eval "$CLAPTRAP_OUTPUT"; }; f; printf '%s' "${claptrap[mode]}""#,
+    );
+    assert_eq!("tls", stdout);
+}
+
+#[test]
+fn bash_eval_declare_all_with_nounset() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            declare-all = true
+            [args]
+            mode = { long = "mode" }
+            files = { long = "file", action = "append" }
+            input = { index = 1 }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec![]);
+    let stdout = bash_eval(
+        &output,
+        r#"printf '%s\0' "$claptrap_mode" "${#claptrap_files[@]}" "$claptrap_input""#,
+    );
+    let values = stdout.split_terminator('\0').collect::<Vec<_>>();
+    assert_eq!(vec!["", "0", ""], values);
+}
+
+#[test]
+fn bash_eval_heredoc_delimiter_in_error() {
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            mode = { long = "mode" }
+        "#,
+    )
+    .unwrap();
+    let output = parse(app, vec!["\nEOF\necho pwned\n".into()]);
+    let status = std::process::Command::new("bash")
+        .arg("-c")
+        .arg(output.to_string())
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(Some(1), status.status.code());
+    let stdout = String::from_utf8_lossy(&status.stdout);
+    assert!(!stdout.lines().any(|line| line == "pwned"));
+}
+
+#[test]
+#[ignore = "requires zsh"]
+fn zsh_spec_file() {
+    require_shell("zsh");
+    let output = std::process::Command::new("tests/resources/zsh_file.zsh")
+        .env("CLAPTRAP_BIN", CLAPTRAP_BIN)
+        .arg("--mode")
+        .arg("stream")
+        .arg("--protocol")
+        .arg("udp")
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(Some(0), output.status.code());
+    insta::assert_snapshot!(String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+#[ignore = "requires zsh"]
+fn zsh_show_usage() {
+    require_shell("zsh");
+    let output = std::process::Command::new("tests/resources/zsh_file.zsh")
+        .env("CLAPTRAP_BIN", CLAPTRAP_BIN)
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(Some(2), output.status.code());
+    insta::assert_snapshot!(String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+#[ignore = "requires zsh"]
+fn zsh_eval_quotes_single_values() {
+    require_shell("zsh");
+    let spec = r#"
+        name = "myapp"
+        [args]
+        value = { long = "value", allow-hyphen-values = true }
+    "#;
+    for &value in HOSTILE_VALUES {
+        let app: Command = toml::from_str(spec).unwrap();
+        let output = parse(app, vec!["--value".into(), value.into()]);
+        let stdout = zsh_eval(&output, r#"printf '%s' "$claptrap_value""#);
+        assert_eq!(value, stdout, "value {value:?} was not preserved");
+    }
+}
+
+#[test]
+#[ignore = "requires zsh"]
+fn zsh_eval_quotes_many_values() {
+    require_shell("zsh");
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            values = { long = "values", action = "append", allow-hyphen-values = true }
+        "#,
+    )
+    .unwrap();
+    let args = HOSTILE_VALUES
+        .iter()
+        .flat_map(|&value| ["--values".into(), OsString::from(value)])
+        .collect();
+    let output = parse(app, args);
+    let stdout = zsh_eval(&output, r#"printf '%s\0' "${claptrap_values[@]}""#);
+    let values = stdout.split_terminator('\0').collect::<Vec<_>>();
+    assert_eq!(HOSTILE_VALUES, values.as_slice());
+}
+
+#[test]
+#[ignore = "requires zsh"]
+fn zsh_eval_non_utf8_values() {
+    require_shell("zsh");
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            values = { long = "values", action = "append", typed-value-parser = "os-string" }
+        "#,
+    )
+    .unwrap();
+    let args = NON_UTF8_VALUES
+        .iter()
+        .flat_map(|&value| ["--values".into(), OsStr::from_bytes(value).to_os_string()])
+        .collect();
+    let output = parse(app, args);
+    let stdout = zsh_eval(
+        &output,
+        r#"
+            printf '%s\0' "${claptrap_values[@]}" | od -An -tx1 -v
+        "#,
+    );
+    assert_eq!(
+        hex_values(NON_UTF8_VALUES),
+        stdout.split_whitespace().join(" ")
+    );
+}
+
+#[test]
+#[ignore = "requires zsh"]
+fn zsh_eval_inside_function() {
+    require_shell("zsh");
+    let spec = include_str!("resources/myapp.toml");
+    let app: Command = toml::from_str(spec).unwrap();
+    let output = parse(app, vec!["--mode".into(), "stream".into()]);
+    let stdout = zsh_eval(
+        &output,
+        r#"unset claptrap_mode; f() { eval "$CLAPTRAP_OUTPUT"; }; f; printf '%s' "$claptrap_mode""#,
+    );
+    assert_eq!("stream", stdout);
+}
+
+#[test]
+#[ignore = "requires fish"]
+fn fish_spec_file() {
+    require_shell("fish");
+    let output = std::process::Command::new("tests/resources/fish_file.fish")
+        .env("CLAPTRAP_BIN", CLAPTRAP_BIN)
+        .arg("--mode")
+        .arg("stream")
+        .arg("--protocol")
+        .arg("udp")
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(Some(0), output.status.code());
+    insta::assert_snapshot!(String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+#[ignore = "requires fish"]
+fn fish_show_usage() {
+    require_shell("fish");
+    let output = std::process::Command::new("tests/resources/fish_file.fish")
+        .env("CLAPTRAP_BIN", CLAPTRAP_BIN)
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(Some(2), output.status.code());
+    insta::assert_snapshot!(String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+#[ignore = "requires fish"]
+fn fish_eval_quotes_single_values() {
+    require_shell("fish");
+    let spec = r#"
+        name = "myapp"
+        [args]
+        value = { long = "value", allow-hyphen-values = true }
+    "#;
+    for &value in HOSTILE_VALUES {
+        let app: Command = toml::from_str(spec).unwrap();
+        let output = parse(app, vec!["--value".into(), value.into()]);
+        let stdout = fish_eval(&output, r#"printf '%s' "$claptrap_value""#);
+        assert_eq!(value, stdout, "value {value:?} was not preserved");
+    }
+}
+
+#[test]
+#[ignore = "requires fish"]
+fn fish_eval_quotes_many_values() {
+    require_shell("fish");
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            values = { long = "values", action = "append", allow-hyphen-values = true }
+        "#,
+    )
+    .unwrap();
+    let args = HOSTILE_VALUES
+        .iter()
+        .flat_map(|&value| ["--values".into(), OsString::from(value)])
+        .collect();
+    let output = parse(app, args);
+    let stdout = fish_eval(&output, "string join0 -- $claptrap_values");
+    let values = stdout.split_terminator('\0').collect::<Vec<_>>();
+    assert_eq!(HOSTILE_VALUES, values.as_slice());
+}
+
+#[test]
+#[ignore = "requires fish"]
+fn fish_eval_non_utf8_values() {
+    require_shell("fish");
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            values = { long = "values", action = "append", typed-value-parser = "os-string" }
+        "#,
+    )
+    .unwrap();
+    let args = NON_UTF8_VALUES
+        .iter()
+        .flat_map(|&value| ["--values".into(), OsStr::from_bytes(value).to_os_string()])
+        .collect();
+    let output = parse(app, args);
+    let stdout = fish_eval(
+        &output,
+        r#"
+            string join0 -- $claptrap_values | od -An -tx1 -v
+        "#,
+    );
+    assert_eq!(
+        hex_values(NON_UTF8_VALUES),
+        stdout.split_whitespace().join(" ")
+    );
+}
+
+#[test]
+fn dash_spec_file() {
+    require_shell("dash");
+    let output = std::process::Command::new("tests/resources/dash_file.sh")
+        .env("CLAPTRAP_BIN", CLAPTRAP_BIN)
+        .arg("--mode")
+        .arg("stream")
+        .arg("--protocol")
+        .arg("udp")
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(Some(0), output.status.code());
     insta::assert_snapshot!(String::from_utf8_lossy(&output.stdout));
 }
+
+#[test]
+fn dash_show_usage() {
+    require_shell("dash");
+    let output = std::process::Command::new("tests/resources/dash_file.sh")
+        .env("CLAPTRAP_BIN", CLAPTRAP_BIN)
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(Some(2), output.status.code());
+    insta::assert_snapshot!(String::from_utf8_lossy(&output.stdout));
+}
+
+#[test]
+fn dash_eval_quotes_single_values() {
+    require_shell("dash");
+    let spec = r#"
+        name = "myapp"
+        [args]
+        value = { long = "value", allow-hyphen-values = true }
+    "#;
+    for &value in HOSTILE_VALUES {
+        let app: Command = toml::from_str(spec).unwrap();
+        let output = parse(app, vec!["--value".into(), value.into()]);
+        let stdout = dash_eval(&output, r#"printf '%s' "$claptrap_value""#);
+        assert_eq!(value, stdout, "value {value:?} was not preserved");
+    }
+}
+
+#[test]
+fn dash_eval_quotes_many_values() {
+    require_shell("dash");
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            values = { long = "values", action = "append", allow-hyphen-values = true }
+        "#,
+    )
+    .unwrap();
+    let args = HOSTILE_VALUES
+        .iter()
+        .flat_map(|&value| ["--values".into(), OsString::from(value)])
+        .collect();
+    let output = parse(app, args);
+    let stdout = dash_eval(
+        &output,
+        r#"
+            i=0
+            while [ "$i" -lt "$claptrap_values_count" ]; do
+                eval "printf '%s\0' \"\$claptrap_values_$i\""
+                i=$((i + 1))
+            done
+        "#,
+    );
+    let values = stdout.split_terminator('\0').collect::<Vec<_>>();
+    assert_eq!(HOSTILE_VALUES, values.as_slice());
+}
+
+#[test]
+fn dash_eval_non_utf8_values() {
+    require_shell("dash");
+    let app: Command = toml::from_str(
+        r#"
+            name = "myapp"
+            [args]
+            values = { long = "values", action = "append", typed-value-parser = "os-string" }
+        "#,
+    )
+    .unwrap();
+    let args = NON_UTF8_VALUES
+        .iter()
+        .flat_map(|&value| ["--values".into(), OsStr::from_bytes(value).to_os_string()])
+        .collect();
+    let output = parse(app, args);
+    let stdout = dash_eval(
+        &output,
+        r#"
+            i=0
+            while [ "$i" -lt "$claptrap_values_count" ]; do
+                eval "printf '%s\0' \"\$claptrap_values_$i\""
+                i=$((i + 1))
+            done | od -An -tx1 -v
+        "#,
+    );
+    assert_eq!(
+        hex_values(NON_UTF8_VALUES),
+        stdout.split_whitespace().join(" ")
+    );
+}
+
+#[test]
+fn json_spec_file() {
+    let output = std::process::Command::new(CLAPTRAP_BIN)
+        .arg("--spec")
+        .arg("tests/resources/myapp.toml")
+        .arg("--format")
+        .arg("json")
+        .arg("--")
+        .arg("--mode")
+        .arg("stream")
+        .arg("--protocol")
+        .arg("udp")
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("variables", json["type"]);
+    assert_eq!("mode", json["args"][0]["id"]);
+    assert_eq!(serde_json::json!([]), json["args"][0]["path"]);
+    assert_eq!("stream", json["args"][0]["value"]);
+    assert_eq!("protocol", json["args"][1]["id"]);
+    assert_eq!("udp", json["args"][1]["value"]);
+}
+
+#[test]
+fn json_show_usage() {
+    let output = std::process::Command::new(CLAPTRAP_BIN)
+        .arg("--spec")
+        .arg("tests/resources/myapp.toml")
+        .arg("--format")
+        .arg("json")
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(Some(0), output.status.code());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!("cat", json["type"]);
+    assert_eq!(2, json["exit_code"]);
+    assert!(json["text"].as_str().unwrap().starts_with("Usage: myapp"));
+}
+
+/// Eval the `output` in bash and then run `script`, returning stdout.
+fn bash_eval(output: &Output, script: &str) -> String {
+    shell_eval(
+        "bash",
+        output.render(OutputFormat::Bash),
+        &format!("set -euo pipefail\neval \"$CLAPTRAP_OUTPUT\"\n{script}"),
+    )
+}
+
+/// Eval the `output` in zsh and then run `script`, returning stdout.
+///
+/// Options which change how quoting and arrays behave are set to ensure the output is
+/// independent of them.
+fn zsh_eval(output: &Output, script: &str) -> String {
+    shell_eval(
+        "zsh",
+        output.render(OutputFormat::Zsh),
+        &format!(
+            "set -euo pipefail\nsetopt rc_quotes ksh_arrays\neval \"$CLAPTRAP_OUTPUT\"\n{script}"
+        ),
+    )
+}
+
+/// Eval the `output` in fish and then run `script`, returning stdout.
+fn fish_eval(output: &Output, script: &str) -> String {
+    shell_eval(
+        "fish",
+        output.render(OutputFormat::Fish),
+        &format!("eval \"$CLAPTRAP_OUTPUT\"\n{script}"),
+    )
+}
+
+/// Eval the `output` in dash and then run `script`, returning stdout.
+fn dash_eval(output: &Output, script: &str) -> String {
+    shell_eval(
+        "dash",
+        output.render(OutputFormat::Posix),
+        &format!("set -eu\neval \"$CLAPTRAP_OUTPUT\"\n{script}"),
+    )
+}
+
+/// Run `script` in `shell` with the rendered `output` in the `CLAPTRAP_OUTPUT` env var.
+fn shell_eval(shell: &str, output: impl Display, script: &str) -> String {
+    let output = std::process::Command::new(shell)
+        .arg("-c")
+        .arg(script)
+        .env("CLAPTRAP_OUTPUT", output.to_string())
+        .output()
+        .expect("Failed to execute command");
+    assert_eq!(Some(0), output.status.code());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+/// The bytes of `values`, each followed by a NUL, as hex in the format of `od -An -tx1`.
+fn hex_values(values: &[&[u8]]) -> String {
+    values
+        .iter()
+        .flat_map(|value| value.iter().chain(&[0]))
+        .map(|byte| format!("{byte:02x}"))
+        .join(" ")
+}
+
+/// Fail the test if `shell` is not installed, rather than passing without running it.
+///
+/// The zsh and fish tests are ignored by default and are run in CI, where all shells are
+/// installed, with `cargo test -- --include-ignored`.
+fn require_shell(shell: &str) {
+    let found = std::process::Command::new(shell)
+        .arg("-c")
+        .arg("true")
+        .output()
+        .is_ok_and(|output| output.status.success());
+    assert!(found, "{shell} is not installed");
+}